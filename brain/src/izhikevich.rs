@@ -0,0 +1,194 @@
+//Izhikevich neurons (Izhikevich 2003, "Simple model of spiking neurons").
+//
+//  v' = 0.04 v^2 + 5 v + 140 - u + I
+//  u' = a (b v - u)
+//  v >= 30 mV: spike, v = c, u += d
+//
+//a, b, c and d are per neuron genes so the GA can evolve bursting,
+//adaptation and fast spiking. The current I comes from the Brain's
+//wiring, like the LIF model.
+
+use crate::{Brain, NeuronModel};
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+
+const V_PEAK: f32 = 30.0;
+
+//per neuron genes
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IzhParams {
+    pub a: f32, //recovery time scale
+    pub b: f32, //recovery sensitivity to v
+    pub c: f32, //reset potential after a spike
+    pub d: f32, //recovery jump after a spike
+}
+
+impl Default for IzhParams {
+    //regular spiking cortical neuron
+    fn default() -> Self {
+        IzhParams {
+            a: 0.02,
+            b: 0.2,
+            c: -65.0,
+            d: 8.0,
+        }
+    }
+}
+
+impl IzhParams {
+    //as in the paper: excitatory neurons spread from regular spiking
+    //to chattering, inhibitory from fast to low threshold spiking
+    pub fn random<R: Rng + ?Sized>(excitatory: bool, rng: &mut R) -> Self {
+        let r: f32 = rng.gen_range(0.0..1.0);
+        if excitatory {
+            IzhParams {
+                a: 0.02,
+                b: 0.2,
+                c: -65.0 + 15.0 * r * r,
+                d: 8.0 - 6.0 * r * r,
+            }
+        } else {
+            IzhParams {
+                a: 0.02 + 0.08 * r,
+                b: 0.25 - 0.05 * r,
+                c: -65.0,
+                d: 2.0,
+            }
+        }
+    }
+
+    //gaussian nudge to one of a, b, c, d, kept in a sane range
+    pub fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let (gene, sigma, low, high) = match rng.gen_range(0..4) {
+            0 => (&mut self.a, 0.01, 0.001, 0.2),
+            1 => (&mut self.b, 0.02, 0.0, 0.3),
+            2 => (&mut self.c, 2.0, -80.0, -40.0),
+            _ => (&mut self.d, 1.0, 0.0, 10.0),
+        };
+        let noise = Normal::new(0.0, sigma).expect("sigma is positive");
+        *gene = (*gene + noise.sample(rng)).max(low).min(high);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IzhikevichModel {
+    pub input_current: f32,     //per connected, active input line
+    pub recurrent_current: f32, //per connected neuron that spiked, sign from xsign
+}
+
+impl Default for IzhikevichModel {
+    fn default() -> Self {
+        IzhikevichModel {
+            input_current: 10.0,
+            recurrent_current: 5.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct IzhState {
+    pub v: Vec<f32>,
+    pub u: Vec<f32>,
+    pub outps: Vec<u8>, //spikes from the last step
+}
+
+impl IzhikevichModel {
+    //a brain without genes of its own runs regular spiking neurons
+    fn params(brain: &Brain, nindex: usize) -> IzhParams {
+        brain.izh.get(nindex).copied().unwrap_or_default()
+    }
+}
+
+impl NeuronModel for IzhikevichModel {
+    type State = IzhState;
+
+    fn rest_state(&self, brain: &Brain) -> IzhState {
+        let v = vec![-65.0; brain.num_neurons];
+        let u = (0..brain.num_neurons)
+            .map(|nindex| IzhikevichModel::params(brain, nindex).b * v[nindex])
+            .collect();
+        IzhState {
+            v,
+            u,
+            outps: vec![0; brain.num_neurons],
+        }
+    }
+
+    fn step<R: Rng + ?Sized>(
+        &self,
+        brain: &Brain,
+        state: &mut IzhState,
+        inputs: &[u8],
+        spikes: &mut [u8],
+        _rng: &mut R,
+    ) {
+        for (nindex, spike) in spikes.iter_mut().enumerate() {
+            let p = IzhikevichModel::params(brain, nindex);
+
+            let mut current = 0.0;
+            for ilink in 0..brain.num_inputs {
+                let inp = inputs.get(ilink).copied().unwrap_or(0) as f32;
+                current += self.input_current * brain.input_weight(nindex, ilink) * inp;
+            }
+            for ilink in 0..brain.num_neurons {
+                let sign = if brain.xsign[ilink] > 0 { 1.0 } else { -1.0 };
+                let outp = state.outps[ilink] as f32;
+                current +=
+                    sign * self.recurrent_current * brain.recurrent_weight(nindex, ilink) * outp;
+            }
+
+            let v = &mut state.v[nindex];
+            let u = &mut state.u[nindex];
+            //two half steps for v, as in the paper, for stability
+            for _ in 0..2 {
+                *v += 0.5 * (0.04 * *v * *v + 5.0 * *v + 140.0 - *u + current);
+            }
+            *u += p.a * (p.b * *v - *u);
+
+            *spike = 0;
+            if *v >= V_PEAK {
+                *spike = 1;
+                *v = p.c;
+                *u += p.d;
+            }
+        } //end of pass through all neurons
+
+        state.outps.copy_from_slice(spikes);
+    } //end of step
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn driven_neurons_spike_and_quiet_ones_rest() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut brain = Brain::random(4, 4, 1, &mut rng).unwrap();
+        brain.nconn = vec![vec![0; 4]; 4];
+        let model = IzhikevichModel::default();
+
+        let mut state = model.rest_state(&brain);
+        let train = brain.run_model(&model, &mut state, &[0; 4], 50, &mut rng);
+        assert_eq!(train.count_group(0..4), 0);
+
+        let mut state = model.rest_state(&brain);
+        let train = brain.run_model(&model, &mut state, &[1, 0, 0, 0], 50, &mut rng);
+        assert!(train.count(0) > 0);
+    }
+
+    #[test]
+    fn mutation_stays_in_range() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut params = IzhParams::random(false, &mut rng);
+        for _ in 0..1000 {
+            params.mutate(&mut rng);
+        }
+        assert!((0.001..=0.2).contains(&params.a));
+        assert!((0.0..=0.3).contains(&params.b));
+        assert!((-80.0..=-40.0).contains(&params.c));
+        assert!((0.0..=10.0).contains(&params.d));
+    }
+}
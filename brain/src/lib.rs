@@ -1,43 +1,259 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
 
-use nannou::rand::random_range;
-extern crate constants;
+mod izhikevich;
+mod lif;
+mod model;
+mod snn;
+mod weights;
+pub use izhikevich::{IzhParams, IzhState, IzhikevichModel};
+pub use lif::{LifModel, LifState};
+pub use model::{Network, NeuronModel};
+pub use snn::{BinaryModel, BinaryParams, NetworkState, SpikeTrain, Wiring};
+pub use weights::WeightMutation;
 
-#[derive(Clone, Debug)]
+//A Floreano style binary network.
+//
+//Sizes are runtime values so brains of different sizes can live
+//side by side. The connection matrices are stored row per neuron:
+//  xsign[n]        1 = excitatory, 0 = inhibitory
+//  iconn[n][i]     input line i feeds neuron n
+//  nconn[n][m]     neuron m feeds neuron n
+//  izh[n]          Izhikevich a, b, c, d of neuron n, empty unless
+//                  the brain was built with_izhikevich()
+//  iweight[n][i]   strength of iconn[n][i], and nweight[n][m] of
+//  nweight[n][m]   nconn[n][m]. Both empty unless with_weights(),
+//                  then every link counts 1, see weights.rs
+//Outputs are read from num_outputs evenly spaced groups of neurons,
+//see output_group().
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Brain {
     pub fitness: f32,
-    pub xsign: [u8; constants::NUM_NEURONS as usize],
-    pub iconn: [[u8; constants::NUM_NEURONS as usize]; constants::NUM_NEURONS as usize],
-    pub nconn: [[u8; constants::NUM_NEURONS as usize]; constants::NUM_NEURONS as usize],
+    #[serde(default)]
+    pub objectives: Vec<f32>, //score of each fitness component, for pareto selection
+    pub num_neurons: usize,
+    pub num_inputs: usize,
+    pub num_outputs: usize,
+    pub xsign: Vec<u8>,
+    pub iconn: Vec<Vec<u8>>,
+    pub nconn: Vec<Vec<u8>>,
+    #[serde(default)]
+    pub izh: Vec<IzhParams>,
+    #[serde(default)]
+    pub iweight: Vec<Vec<f32>>,
+    #[serde(default)]
+    pub nweight: Vec<Vec<f32>>,
 }
 
 impl Brain {
-    pub fn new() -> Self {
+    //random signs and recurrent links, every input connected.
+    //all randomness comes from the caller's rng so runs can be repeated
+    pub fn random<R: Rng + ?Sized>(
+        num_neurons: usize,
+        num_inputs: usize,
+        num_outputs: usize,
+        rng: &mut R,
+    ) -> Result<Self, String> {
+        Brain::check_sizes(num_neurons, num_inputs, num_outputs)?;
         let fitness = 0.0;
-        let mut xsign = [0; constants::NUM_NEURONS];
-        for ix in 0..constants::NUM_NEURONS {
-            xsign[ix] = random_range(0, 2) as u8;
-        }
-        let iconn = [[1; constants::NUM_NEURONS as usize]; constants::NUM_NEURONS as usize];
-        let mut nconn = [[0; constants::NUM_NEURONS as usize]; constants::NUM_NEURONS as usize];
-        for ix in 0..constants::NUM_NEURONS {
-            for iy in 0..constants::NUM_NEURONS {
-                nconn[ix][iy] = random_range(0, 2);
+        let mut xsign = vec![0; num_neurons];
+        for sign in xsign.iter_mut() {
+            *sign = rng.gen_range(0..2) as u8;
+        }
+        let iconn = vec![vec![1; num_inputs]; num_neurons];
+        let mut nconn = vec![vec![0; num_neurons]; num_neurons];
+        for row in nconn.iter_mut() {
+            for link in row.iter_mut() {
+                *link = rng.gen_range(0..2);
             }
         }
-                Brain {
+        Ok(Brain {
             fitness,
+            objectives: Vec::new(),
+            num_neurons,
+            num_inputs,
+            num_outputs,
             xsign,
             iconn,
             nconn,
-        }   
-    } //end of new
-} //end of impl Brain
+            izh: Vec::new(),
+            iweight: Vec::new(),
+            nweight: Vec::new(),
+        })
+    } //end of random
+
+    //give every neuron Izhikevich genes to match its sign
+    pub fn with_izhikevich<R: Rng + ?Sized>(mut self, rng: &mut R) -> Self {
+        self.izh = self
+            .xsign
+            .iter()
+            .map(|&sign| IzhParams::random(sign > 0, rng))
+            .collect();
+        self
+    }
+
+    pub fn check_sizes(
+        num_neurons: usize,
+        num_inputs: usize,
+        num_outputs: usize,
+    ) -> Result<(), String> {
+        if num_neurons == 0 || num_inputs == 0 || num_outputs == 0 {
+            return Err("a brain needs at least one neuron, input and output".to_string());
+        }
+        if num_outputs > num_neurons {
+            return Err(format!(
+                "{} outputs but only {} neurons to read them from",
+                num_outputs, num_neurons
+            ));
+        }
+        Ok(())
+    }
+
+    //the matrices have to agree with the sizes and hold only 0/1,
+    //weights (if any) must be finite and not negative
+    pub fn validate(&self) -> Result<(), String> {
+        Brain::check_sizes(self.num_neurons, self.num_inputs, self.num_outputs)?;
+        if self.xsign.len() != self.num_neurons {
+            return Err(format!(
+                "xsign has {} entries, expected {}",
+                self.xsign.len(),
+                self.num_neurons
+            ));
+        }
+        if self.iconn.len() != self.num_neurons
+            || self.iconn.iter().any(|row| row.len() != self.num_inputs)
+        {
+            return Err(format!(
+                "iconn must be {} x {}",
+                self.num_neurons, self.num_inputs
+            ));
+        }
+        if self.nconn.len() != self.num_neurons
+            || self.nconn.iter().any(|row| row.len() != self.num_neurons)
+        {
+            return Err(format!(
+                "nconn must be {} x {}",
+                self.num_neurons, self.num_neurons
+            ));
+        }
+        if !self.izh.is_empty() && self.izh.len() != self.num_neurons {
+            return Err(format!(
+                "izh has {} entries, expected {}",
+                self.izh.len(),
+                self.num_neurons
+            ));
+        }
+        self.check_weights()?;
+        let bits = self
+            .xsign
+            .iter()
+            .chain(self.iconn.iter().flatten())
+            .chain(self.nconn.iter().flatten());
+        if bits.copied().any(|bit| bit > 1) {
+            return Err("connections and signs must be 0 or 1".to_string());
+        }
+        Ok(())
+    }
+
+    //same genes, whatever the fitness
+    pub fn same_genome(&self, other: &Brain) -> bool {
+        self.xsign == other.xsign
+            && self.iconn == other.iconn
+            && self.nconn == other.nconn
+            && self.izh == other.izh
+            && self.iweight == other.iweight
+            && self.nweight == other.nweight
+    }
+
+    //fraction of the sign and link bits that differ, 0 for the
+    //same wiring and 1 for the opposite. izh and weights are left out
+    pub fn distance(&self, other: &Brain) -> f32 {
+        let bits = |b: &Brain| -> Vec<u8> {
+            let mut bits = b.xsign.clone();
+            bits.extend(b.iconn.iter().flatten());
+            bits.extend(b.nconn.iter().flatten());
+            bits
+        };
+        let (a, b) = (bits(self), bits(other));
+        if a.is_empty() || a.len() != b.len() {
+            return if a == b { 0.0 } else { 1.0 };
+        }
+        let differ = a.iter().zip(&b).filter(|(x, y)| x != y).count();
+        differ as f32 / a.len() as f32
+    }
 
+    //neurons read for output iout. Groups are num_neurons / num_outputs
+    //wide and spread evenly, for 8 neurons and 3 outputs that is
+    //0..2, 3..5 and 6..8 as in the original 8 bit brain.
+    pub fn output_group(&self, iout: usize) -> Range<usize> {
+        let width = self.num_neurons / self.num_outputs;
+        let start = if self.num_outputs > 1 {
+            iout * (self.num_neurons - width) / (self.num_outputs - 1)
+        } else {
+            0
+        };
+        start..start + width
+    }
+} //end of impl Brain
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn random_brain_is_binary_and_fully_wired() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        for &size in &[8, 16, 64] {
+            let brain = Brain::random(size, size, 3, &mut rng).unwrap();
+            assert_eq!(brain.fitness, 0.0);
+            assert_eq!(brain.xsign.len(), size);
+            assert!(brain.xsign.iter().all(|&s| s <= 1));
+            assert!(brain.nconn.iter().flatten().all(|&c| c <= 1));
+            assert!(brain.iconn.iter().flatten().all(|&c| c == 1));
+            assert!(brain.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn distance_counts_differing_bits() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        let brain = Brain::random(8, 8, 3, &mut rng).unwrap();
+        let mut other = brain.clone();
+        assert_eq!(brain.distance(&other), 0.0);
+        other.xsign[0] ^= 1;
+        other.nconn[1][2] ^= 1;
+        //8 signs, 64 input links and 64 recurrent links
+        assert_eq!(brain.distance(&other), 2.0 / 136.0);
+        let small = Brain::random(4, 4, 3, &mut rng).unwrap();
+        assert_eq!(brain.distance(&small), 1.0);
+    }
+
+    #[test]
+    fn bad_sizes_are_rejected() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        assert!(Brain::random(0, 8, 3, &mut rng).is_err());
+        assert!(Brain::random(2, 8, 3, &mut rng).is_err());
+
+        let mut brain = Brain::random(8, 4, 3, &mut rng).unwrap();
+        brain.iconn[2].push(1);
+        assert!(brain.validate().is_err());
+    }
+
+    #[test]
+    fn output_groups_match_the_8_bit_brain() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let brain = Brain::random(8, 8, 3, &mut rng).unwrap();
+        let groups: Vec<_> = (0..3).map(|i| brain.output_group(i)).collect();
+        assert_eq!(groups, vec![0..2, 3..5, 6..8]);
+
+        let brain = Brain::random(64, 64, 3, &mut rng).unwrap();
+        assert_eq!(brain.output_group(2), 43..64);
+    }
 }
@@ -0,0 +1,125 @@
+//Leaky integrate and fire neurons with f32 membranes.
+//
+//A more biological alternative to the binary rule, on the same
+//genome. Each step
+//  v += (v_rest - v) / tau + input
+//where input is the weighted sum over connected input lines and
+//over neurons that spiked last step (signed by their xsign).
+//A neuron at or above threshold spikes, drops to v_reset and
+//ignores input for refractory_period steps.
+
+use crate::{Brain, NeuronModel};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LifModel {
+    pub tau: f32, //membrane time constant, in steps
+    pub v_rest: f32,
+    pub v_reset: f32,
+    pub threshold: f32,
+    pub refractory_period: u32, //absolute, in steps
+    pub input_weight: f32,      //per connected input line
+    pub recurrent_weight: f32,  //per connected neuron, sign from xsign
+}
+
+impl Default for LifModel {
+    fn default() -> Self {
+        LifModel {
+            tau: 5.0,
+            v_rest: 0.0,
+            v_reset: 0.0,
+            threshold: 1.0,
+            refractory_period: 1,
+            input_weight: 0.3,
+            recurrent_weight: 0.2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LifState {
+    pub v: Vec<f32>,
+    pub refractory: Vec<u32>,
+    pub outps: Vec<u8>, //spikes from the last step
+}
+
+impl NeuronModel for LifModel {
+    type State = LifState;
+
+    fn rest_state(&self, brain: &Brain) -> LifState {
+        LifState {
+            v: vec![self.v_rest; brain.num_neurons],
+            refractory: vec![0; brain.num_neurons],
+            outps: vec![0; brain.num_neurons],
+        }
+    }
+
+    fn step<R: Rng + ?Sized>(
+        &self,
+        brain: &Brain,
+        state: &mut LifState,
+        inputs: &[u8],
+        spikes: &mut [u8],
+        _rng: &mut R,
+    ) {
+        for (nindex, spike) in spikes.iter_mut().enumerate() {
+            *spike = 0;
+            if state.refractory[nindex] > 0 {
+                state.refractory[nindex] -= 1;
+                state.v[nindex] = self.v_reset;
+                continue;
+            }
+
+            let mut current = 0.0;
+            for ilink in 0..brain.num_inputs {
+                let inp = inputs.get(ilink).copied().unwrap_or(0) as f32;
+                current += self.input_weight * brain.input_weight(nindex, ilink) * inp;
+            }
+            for ilink in 0..brain.num_neurons {
+                let sign = if brain.xsign[ilink] > 0 { 1.0 } else { -1.0 };
+                let outp = state.outps[ilink] as f32;
+                current +=
+                    sign * self.recurrent_weight * brain.recurrent_weight(nindex, ilink) * outp;
+            }
+
+            let v = &mut state.v[nindex];
+            *v += (self.v_rest - *v) / self.tau + current;
+            if *v >= self.threshold {
+                *spike = 1;
+                *v = self.v_reset;
+                state.refractory[nindex] = self.refractory_period;
+            }
+        } //end of pass through all neurons
+
+        state.outps.copy_from_slice(spikes);
+    } //end of step
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn input_charges_the_membrane_until_it_fires() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut brain = Brain::random(4, 4, 1, &mut rng).unwrap();
+        brain.nconn = vec![vec![0; 4]; 4];
+        let model = LifModel::default();
+        let mut state = model.rest_state(&brain);
+
+        //one active line gives 0.3 a step, v goes 0.3, 0.54, 0.73, 0.89, 1.01
+        let train = brain.run_model(&model, &mut state, &[1, 0, 0, 0], 6, &mut rng);
+        let first: Vec<u8> = train.raster.iter().map(|step| step[0]).collect();
+        assert_eq!(first, vec![0, 0, 0, 0, 1, 0]);
+        assert_eq!(state.v[0], model.v_reset);
+
+        //nothing in, nothing out, and the membrane relaxes to rest
+        let mut state = model.rest_state(&brain);
+        state.v[1] = 0.5;
+        let train = brain.run_model(&model, &mut state, &[0; 4], 5, &mut rng);
+        assert_eq!(train.count_group(0..4), 0);
+        assert!(state.v[1] < 0.5 && state.v[1] > model.v_rest);
+    }
+}
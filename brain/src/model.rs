@@ -0,0 +1,98 @@
+//Neuron models.
+//
+//A NeuronModel says how one pass over the network turns inputs
+//and the previous state into spikes. The Brain supplies the wiring,
+//the model the dynamics. Network bundles a model with its state so
+//a run can pick the model from its config.
+
+use crate::{
+    BinaryModel, Brain, IzhState, IzhikevichModel, LifModel, LifState, NetworkState, SpikeTrain,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+pub trait NeuronModel {
+    type State: Clone + Debug;
+
+    //state with every neuron at rest
+    fn rest_state(&self, brain: &Brain) -> Self::State;
+
+    //one pass over all neurons, spikes[n] is set to 1 if n fired
+    fn step<R: Rng + ?Sized>(
+        &self,
+        brain: &Brain,
+        state: &mut Self::State,
+        inputs: &[u8],
+        spikes: &mut [u8],
+        rng: &mut R,
+    );
+}
+
+impl Brain {
+    //run any model for steps passes with the same inputs
+    pub fn run_model<M: NeuronModel, R: Rng + ?Sized>(
+        &self,
+        model: &M,
+        state: &mut M::State,
+        inputs: &[u8],
+        steps: usize,
+        rng: &mut R,
+    ) -> SpikeTrain {
+        let mut raster = Vec::with_capacity(steps);
+        for _epoch in 0..steps {
+            let mut spikes = vec![0u8; self.num_neurons];
+            model.step(self, state, inputs, &mut spikes, rng);
+            raster.push(spikes);
+        }
+        SpikeTrain { raster }
+    }
+}
+
+//a model picked at run time, with its state
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Network {
+    Binary(BinaryModel, NetworkState),
+    Lif(LifModel, LifState),
+    Izhikevich(IzhikevichModel, IzhState),
+}
+
+impl Network {
+    pub fn binary(model: BinaryModel, brain: &Brain) -> Self {
+        let state = model.rest_state(brain);
+        Network::Binary(model, state)
+    }
+
+    pub fn lif(model: LifModel, brain: &Brain) -> Self {
+        let state = model.rest_state(brain);
+        Network::Lif(model, state)
+    }
+
+    pub fn izhikevich(model: IzhikevichModel, brain: &Brain) -> Self {
+        let state = model.rest_state(brain);
+        Network::Izhikevich(model, state)
+    }
+
+    //back to rest, sized for brain
+    pub fn reset(&mut self, brain: &Brain) {
+        match self {
+            Network::Binary(model, state) => *state = model.rest_state(brain),
+            Network::Lif(model, state) => *state = model.rest_state(brain),
+            Network::Izhikevich(model, state) => *state = model.rest_state(brain),
+        }
+    }
+
+    pub fn run<R: Rng + ?Sized>(
+        &mut self,
+        brain: &Brain,
+        inputs: &[u8],
+        steps: usize,
+        rng: &mut R,
+    ) -> SpikeTrain {
+        match self {
+            Network::Binary(model, state) => brain.run_model(model, state, inputs, steps, rng),
+            Network::Lif(model, state) => brain.run_model(model, state, inputs, steps, rng),
+            Network::Izhikevich(model, state) => brain.run_model(model, state, inputs, steps, rng),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//The spiking network update, on its own.
+//
+//This is the binary integrate and fire rule the rover has always
+//used (see the Floreano paper). It used to live inside
+//Mover::think, now anything with an input vector can drive a Brain.
+
+use crate::{Brain, NeuronModel};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+//which neurons a link reads from.
+//The original rule read the neuron's own input line and its own
+//last output on every link, and as a neuron that just fired is
+//refractory the recurrent term always came out 0. Legacy keeps that
+//rule, and with refractory_period = 1 (the default) it is the
+//original update exactly, so old runs repeat; a longer refractory
+//period changes it. Presynaptic sums input line i over iconn[n][i]
+//and the last spike of neuron m over nconn[n][m].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Wiring {
+    Legacy,
+    Presynaptic,
+}
+
+//knobs of the binary neuron rule
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BinaryParams {
+    pub threshold: i32,
+    pub threshold_noise: i32, //threshold moves by -noise..=noise each step
+    pub leaking_constant: u8,
+    pub refractory_period: u8, //steps a neuron ignores input after firing
+    pub wiring: Wiring,
+}
+
+impl Default for BinaryParams {
+    fn default() -> Self {
+        BinaryParams {
+            threshold: 3,
+            threshold_noise: 2,
+            leaking_constant: 1,
+            refractory_period: 1,
+            wiring: Wiring::Legacy,
+        }
+    }
+}
+
+//spike raster, raster[step][neuron] is 1 if the neuron fired
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpikeTrain {
+    pub raster: Vec<Vec<u8>>,
+}
+
+impl SpikeTrain {
+    pub fn steps(&self) -> usize {
+        self.raster.len()
+    }
+
+    //spikes of one neuron over the whole run
+    pub fn count(&self, neuron: usize) -> u32 {
+        self.raster.iter().map(|step| step[neuron] as u32).sum()
+    }
+
+    //spikes of a group of neurons, e.g. Brain::output_group()
+    pub fn count_group(&self, neurons: Range<usize>) -> u32 {
+        neurons.map(|neuron| self.count(neuron)).sum()
+    }
+}
+
+//what the network carries from one step (and one tick) to the next
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkState {
+    pub memb: Vec<u8>,       //membrane potentials
+    pub refractory: Vec<u8>, //steps left before a neuron listens again
+    pub outps: Vec<u8>,      //spikes from the last step
+}
+
+impl NetworkState {
+    //everything at rest
+    pub fn new(num_neurons: usize) -> Self {
+        NetworkState {
+            memb: vec![0; num_neurons],
+            refractory: vec![0; num_neurons],
+            outps: vec![0; num_neurons],
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = NetworkState::new(self.memb.len());
+    }
+}
+
+//the binary rule as a NeuronModel. clear_membrane works the
+//membrane out fresh every step (the original rule), otherwise it
+//builds up and only drains by leak and by firing.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BinaryModel {
+    pub params: BinaryParams,
+    pub clear_membrane: bool,
+}
+
+impl NeuronModel for BinaryModel {
+    type State = NetworkState;
+
+    fn rest_state(&self, brain: &Brain) -> NetworkState {
+        NetworkState::new(brain.num_neurons)
+    }
+
+    fn step<R: Rng + ?Sized>(
+        &self,
+        brain: &Brain,
+        state: &mut NetworkState,
+        inputs: &[u8],
+        spikes: &mut [u8],
+        rng: &mut R,
+    ) {
+        let params = &self.params;
+        let NetworkState {
+            memb,
+            refractory,
+            outps,
+        } = state;
+
+        for nindex in 0..brain.num_neurons {
+            if self.clear_membrane {
+                memb[nindex] = 0;
+            }
+            //the neuron a link reads from, see Wiring
+            let source = |ilink: usize| match params.wiring {
+                Wiring::Legacy => nindex,
+                Wiring::Presynaptic => ilink,
+            };
+            if refractory[nindex] == 0 {
+                //not in refactory state
+                for ilink in 0..brain.num_inputs {
+                    let inp = inputs.get(source(ilink)).copied().unwrap_or(0);
+                    //weights are rounded, the membrane only counts whole spikes
+                    let stuff = (inp as f32 * brain.input_weight(nindex, ilink)).round() as u8;
+                    memb[nindex] = memb[nindex].saturating_add(stuff);
+                } //end of loop on ilink
+                  //count from other neurons with positive sign
+                for ilink in 0..brain.num_neurons {
+                    let outp = outps[source(ilink)];
+                    let stuff = (outp as f32 * brain.recurrent_weight(nindex, ilink)).round() as u8;
+                    if brain.xsign[ilink] > 0 {
+                        //positives
+                        memb[nindex] = memb[nindex].saturating_add(stuff);
+                    }
+                    if brain.xsign[ilink] == 0 {
+                        //negatives
+                        if stuff <= memb[nindex] {
+                            memb[nindex] -= stuff;
+                        } else {
+                            memb[nindex] = 0;
+                        } //end of if on sign less than 0
+                    } //end of if on <=0
+                } //end of loop on ilink
+            } else {
+                refractory[nindex] -= 1;
+            } //end of not refactory
+
+            //fire or not !
+            let noise = params.threshold_noise;
+            let r: i32 = rng.gen_range(-noise..=noise);
+            if memb[nindex] as i32 >= (params.threshold + r) {
+                spikes[nindex] = 1;
+                memb[nindex] = 0;
+                refractory[nindex] = params.refractory_period;
+            } else {
+                spikes[nindex] = 0;
+            }
+            //leakage
+            if memb[nindex] >= params.leaking_constant {
+                memb[nindex] -= params.leaking_constant;
+            }
+        } //end of pass through all neurons
+
+        outps.copy_from_slice(spikes);
+    } //end of step
+}
+
+impl Brain {
+    //run the network for steps passes with the same inputs.
+    //
+    //Neurons start at rest and the membrane is worked out fresh
+    //every step, so nothing is remembered between calls.
+    //With Wiring::Legacy neuron n sees input line n, with
+    //Presynaptic every line i it has iconn[n][i] set for. Inputs
+    //past the end read as 0. Noise on the threshold comes from rng.
+    pub fn simulate<R: Rng + ?Sized>(
+        &self,
+        inputs: &[u8],
+        steps: usize,
+        params: &BinaryParams,
+        rng: &mut R,
+    ) -> SpikeTrain {
+        let model = BinaryModel {
+            params: params.clone(),
+            clear_membrane: true,
+        };
+        let mut state = model.rest_state(self);
+        self.run_model(&model, &mut state, inputs, steps, rng)
+    }
+
+    //same rule, but the network carries on from state and leaves
+    //its final state there. The membrane is only drained by the
+    //leak and by firing, so potentials build up across steps and
+    //across calls. Reset the state at the start of each life.
+    pub fn simulate_stateful<R: Rng + ?Sized>(
+        &self,
+        state: &mut NetworkState,
+        inputs: &[u8],
+        steps: usize,
+        params: &BinaryParams,
+        rng: &mut R,
+    ) -> SpikeTrain {
+        let model = BinaryModel {
+            params: params.clone(),
+            clear_membrane: false,
+        };
+        if state.memb.len() != self.num_neurons {
+            *state = model.rest_state(self);
+        }
+        self.run_model(&model, state, inputs, steps, rng)
+    }
+} //end of impl Brain
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn quiet_params() -> BinaryParams {
+        BinaryParams {
+            threshold_noise: 0,
+            ..BinaryParams::default()
+        }
+    }
+
+    #[test]
+    fn silent_without_input() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let brain = Brain::random(8, 8, 3, &mut rng).unwrap();
+        let train = brain.simulate(&[0; 8], 20, &quiet_params(), &mut rng);
+        assert_eq!(train.steps(), 20);
+        assert_eq!(train.count_group(0..8), 0);
+    }
+
+    #[test]
+    fn driven_neuron_fires_every_other_step() {
+        //8 connected inputs push the membrane past the threshold,
+        //then the neuron is refractory for one step.
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let brain = Brain::random(8, 8, 3, &mut rng).unwrap();
+        let mut inputs = [0; 8];
+        inputs[2] = 1;
+        let train = brain.simulate(&inputs, 10, &quiet_params(), &mut rng);
+        assert_eq!(train.count(2), 5);
+        assert_eq!(train.raster[0][2], 1);
+        assert_eq!(train.raster[1][2], 0);
+        assert_eq!(train.count_group(0..8), 5);
+    }
+
+    #[test]
+    fn stateful_membrane_builds_up_across_calls() {
+        //8 a step against a threshold of 20: only an integrating
+        //membrane gets there, on the third step.
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let brain = Brain::random(8, 8, 3, &mut rng).unwrap();
+        let params = BinaryParams {
+            threshold: 20,
+            ..quiet_params()
+        };
+        let mut inputs = [0; 8];
+        inputs[2] = 1;
+
+        let train = brain.simulate(&inputs, 3, &params, &mut rng);
+        assert_eq!(train.count(2), 0);
+
+        let mut state = NetworkState::new(8);
+        let train = brain.simulate_stateful(&mut state, &inputs, 2, &params, &mut rng);
+        assert_eq!(train.count(2), 0);
+        assert_eq!(state.memb[2], 14);
+        let train = brain.simulate_stateful(&mut state, &inputs, 1, &params, &mut rng);
+        assert_eq!(train.raster[0][2], 1);
+        assert_eq!(state.refractory[2], 1);
+
+        state.reset();
+        assert_eq!(state, NetworkState::new(8));
+    }
+
+    #[test]
+    fn presynaptic_wiring_passes_spikes_on() {
+        //input 0 only feeds neuron 0, which only feeds neuron 1.
+        //legacy wiring never gets past neuron 0.
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut brain = Brain::random(4, 4, 1, &mut rng).unwrap();
+        brain.xsign = vec![1; 4];
+        brain.iconn = vec![vec![0; 4]; 4];
+        brain.iconn[0][0] = 1;
+        brain.nconn = vec![vec![0; 4]; 4];
+        brain.nconn[1][0] = 1;
+        let params = BinaryParams {
+            threshold: 1,
+            ..quiet_params()
+        };
+        let inputs = [1, 0, 0, 0];
+
+        let train = brain.simulate(&inputs, 4, &params, &mut rng);
+        assert_eq!(train.count(0), 2);
+        assert_eq!(train.count_group(1..4), 0);
+
+        let params = BinaryParams {
+            wiring: Wiring::Presynaptic,
+            ..params
+        };
+        let train = brain.simulate(&inputs, 4, &params, &mut rng);
+        assert_eq!(train.raster[0], vec![1, 0, 0, 0]);
+        assert_eq!(train.raster[1], vec![0, 1, 0, 0]);
+        assert_eq!(train.count_group(2..4), 0);
+    }
+}
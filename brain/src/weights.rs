@@ -0,0 +1,144 @@
+//Optional synaptic weights.
+//
+//A brain without weights behaves as before: every connection bit
+//counts 1. with_weights() gives each input and recurrent connection
+//a strength in 0..=max that the GA can tune. The sign of a recurrent
+//link still comes from xsign of the sending neuron, and a 0 bit in
+//iconn/nconn still cuts the link whatever its weight.
+
+use crate::Brain;
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+
+//how a single weight is changed by the GA
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeightMutation {
+    pub sigma: f32,      //std dev of the gaussian nudge
+    pub max: f32,        //weights stay in 0..=max
+    pub reset_rate: f64, //chance of a fresh uniform weight instead of a nudge
+}
+
+impl Default for WeightMutation {
+    fn default() -> Self {
+        WeightMutation {
+            sigma: 0.3,
+            max: 3.0,
+            reset_rate: 0.1,
+        }
+    }
+}
+
+impl WeightMutation {
+    pub fn apply<R: Rng + ?Sized>(&self, weight: &mut f32, rng: &mut R) {
+        if rng.gen_bool(self.reset_rate) {
+            *weight = rng.gen_range(0.0..=self.max);
+        } else {
+            let noise = Normal::new(0.0, self.sigma).expect("sigma is positive");
+            *weight = (*weight + noise.sample(rng)).max(0.0).min(self.max);
+        }
+    }
+}
+
+impl Brain {
+    //uniform weights in 0..=max on every input and recurrent link
+    pub fn with_weights<R: Rng + ?Sized>(mut self, max: f32, rng: &mut R) -> Self {
+        let mut draw =
+            |len: usize| -> Vec<f32> { (0..len).map(|_| rng.gen_range(0.0..=max)).collect() };
+        self.iweight = (0..self.num_neurons)
+            .map(|_| draw(self.num_inputs))
+            .collect();
+        self.nweight = (0..self.num_neurons)
+            .map(|_| draw(self.num_neurons))
+            .collect();
+        self
+    }
+
+    pub fn is_weighted(&self) -> bool {
+        !self.iweight.is_empty()
+    }
+
+    //strength of input line ilink onto neuron nindex, 0 if not connected
+    pub fn input_weight(&self, nindex: usize, ilink: usize) -> f32 {
+        if self.iconn[nindex][ilink] == 0 {
+            return 0.0;
+        }
+        self.iweight.get(nindex).map_or(1.0, |row| row[ilink])
+    }
+
+    //strength of neuron ilink onto neuron nindex, 0 if not connected
+    pub fn recurrent_weight(&self, nindex: usize, ilink: usize) -> f32 {
+        if self.nconn[nindex][ilink] == 0 {
+            return 0.0;
+        }
+        self.nweight.get(nindex).map_or(1.0, |row| row[ilink])
+    }
+
+    //nudge or reset one random input or recurrent weight
+    pub fn mutate_weight<R: Rng + ?Sized>(&mut self, mutation: &WeightMutation, rng: &mut R) {
+        if !self.is_weighted() {
+            return;
+        }
+        let nindex = rng.gen_range(0..self.num_neurons);
+        let weight = if rng.gen_bool(0.5) {
+            let ilink = rng.gen_range(0..self.num_inputs);
+            &mut self.iweight[nindex][ilink]
+        } else {
+            let ilink = rng.gen_range(0..self.num_neurons);
+            &mut self.nweight[nindex][ilink]
+        };
+        mutation.apply(weight, rng);
+    }
+
+    pub(crate) fn check_weights(&self) -> Result<(), String> {
+        if !self.is_weighted() && self.nweight.is_empty() {
+            return Ok(());
+        }
+        if self.iweight.len() != self.num_neurons
+            || self.iweight.iter().any(|row| row.len() != self.num_inputs)
+        {
+            return Err(format!(
+                "iweight must be {} x {}",
+                self.num_neurons, self.num_inputs
+            ));
+        }
+        if self.nweight.len() != self.num_neurons
+            || self.nweight.iter().any(|row| row.len() != self.num_neurons)
+        {
+            return Err(format!(
+                "nweight must be {} x {}",
+                self.num_neurons, self.num_neurons
+            ));
+        }
+        let weights = self.iweight.iter().chain(self.nweight.iter()).flatten();
+        if weights.copied().any(|w| !w.is_finite() || w < 0.0) {
+            return Err("weights must be finite and not negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn unweighted_links_count_one_and_cut_links_zero() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut brain = Brain::random(4, 4, 1, &mut rng).unwrap();
+        brain.iconn[0][1] = 0;
+        assert_eq!(brain.input_weight(0, 0), 1.0);
+        assert_eq!(brain.input_weight(0, 1), 0.0);
+
+        let mut brain = brain.with_weights(2.0, &mut rng);
+        assert!(brain.validate().is_ok());
+        assert_eq!(brain.input_weight(0, 1), 0.0);
+        let mutation = WeightMutation::default();
+        for _ in 0..1000 {
+            brain.mutate_weight(&mutation, &mut rng);
+        }
+        assert!(brain.validate().is_ok());
+        assert!(brain.iweight.iter().flatten().all(|&w| w <= mutation.max));
+    }
+}
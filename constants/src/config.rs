@@ -0,0 +1,779 @@
+//Runtime knobs for a simulation run.
+//
+//The consts in lib.rs are the defaults. A config file (TOML or JSON,
+//picked by extension) only needs the keys it changes, and single
+//keys can be overridden from the command line with key=value.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::path::{Path, PathBuf};
+
+//which neuron rule drives the brains
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NeuronKind {
+    Binary,     //Floreano style u8 membranes, threshold/leak/noise below
+    Lif,        //f32 leaky integrate and fire, lif_* below
+    Izhikevich, //per neuron a, b, c, d genes, izh_* below
+}
+
+//how the binary rule reads its links, see brain::Wiring
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WiringKind {
+    Legacy,      //the original update with refractory_period = 1, recurrent term always 0
+    Presynaptic, //sums presynaptic inputs and spikes
+}
+
+//how the next brain is picked at the end of a life
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GaKind {
+    Steady,       //the original: roulette pick and replace the worst, every life
+    Generational, //evaluate the whole population, then breed a new one
+}
+
+//where the trials of a brain start
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartKind {
+    Fixed,      //start_positions in turn, or the original spot
+    Random,     //anywhere clear in the arena
+    Stratified, //one cell each of a grid over the arena
+}
+
+//how the trial scores of a brain make its fitness
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AggregateKind {
+    Mean,
+    Min,
+    Median,
+}
+
+//something in the arena the rover dies on and the sensors see.
+//arena coordinates, the origin is in the middle
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self", rename_all = "lowercase")]
+pub enum Obstacle {
+    Box { x: f32, y: f32, w: f32, h: f32 }, //centered on x, y
+    Circle { x: f32, y: f32, r: f32 },
+    Polygon { points: Vec<[f32; 2]> }, //convex, either winding
+    Segment { from: [f32; 2], to: [f32; 2] },
+}
+
+//in a config or map file an obstacle is a table with
+//shape = "box" and so on. bincode checkpoints can't read a
+//tagged enum, so they get the plain one derived above
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Obstacle", tag = "shape", rename_all = "lowercase")]
+enum Tagged {
+    Box { x: f32, y: f32, w: f32, h: f32 },
+    Circle { x: f32, y: f32, r: f32 },
+    Polygon { points: Vec<[f32; 2]> },
+    Segment { from: [f32; 2], to: [f32; 2] },
+}
+
+impl Serialize for Obstacle {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            Tagged::serialize(self, serializer)
+        } else {
+            Obstacle::serialize(self, serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Obstacle {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            Tagged::deserialize(deserializer)
+        } else {
+            Obstacle::deserialize(deserializer)
+        }
+    }
+}
+
+impl Obstacle {
+    //x, y on or in it. a segment is SEGMENT_WIDTH thick so a
+    //diagonal step can't hop over it
+    pub fn contains(&self, px: f32, py: f32) -> bool {
+        match self {
+            Obstacle::Box { x, y, w, h } => {
+                (x - w / 2.0..=x + w / 2.0).contains(&px)
+                    && (y - h / 2.0..=y + h / 2.0).contains(&py)
+            }
+            Obstacle::Circle { x, y, r } => (px - x).hypot(py - y) <= *r,
+            Obstacle::Polygon { points } => {
+                //same side of every edge, whichever way round they go
+                let n = points.len();
+                let sides: Vec<f32> = (0..n)
+                    .map(|i| {
+                        let [ax, ay] = points[i];
+                        let [bx, by] = points[(i + 1) % n];
+                        (bx - ax) * (py - ay) - (by - ay) * (px - ax)
+                    })
+                    .collect();
+                sides.iter().all(|&s| s >= 0.0) || sides.iter().all(|&s| s <= 0.0)
+            }
+            Obstacle::Segment { from, to } => {
+                let [ax, ay] = *from;
+                let (dx, dy) = (to[0] - ax, to[1] - ay);
+                let t = (((px - ax) * dx + (py - ay) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+                (px - ax - t * dx).hypot(py - ay - t * dy) <= crate::SEGMENT_WIDTH / 2.0
+            }
+        }
+    }
+}
+
+//the original 100 by 100 box in the middle
+pub fn default_obstacles() -> Vec<Obstacle> {
+    vec![Obstacle::Box {
+        x: 0.0,
+        y: 0.0,
+        w: 100.0,
+        h: 100.0,
+    }]
+}
+
+//a map file: just the obstacles, the walls are width by height
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Map {
+    obstacles: Vec<Obstacle>,
+}
+
+//how parents are picked by the generational GA
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionKind {
+    Roulette,   //fitness proportional
+    Tournament, //best of tournament_size
+    Rank,       //linear ranking, rank_pressure
+    Truncation, //uniform among the best truncation fraction
+    Sus,        //stochastic universal sampling
+    Nsga2,      //pareto fronts over the fitness components
+}
+
+//how the generational GA mixes two parents
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrossoverKind {
+    None,     //children copy their first parent
+    Uniform,  //every gene from either parent
+    Neuron,   //every neuron with its incoming links from either parent
+    OnePoint, //flattened genome cut once
+    TwoPoint, //flattened genome cut twice
+}
+
+//how children (or, with the steady GA, the next brain) are changed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MutationKind {
+    Count, //a fixed number of changes, *_mutations
+    Rate,  //every gene on its own, *_rate
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimConfig {
+    pub width: f32,
+    pub height: f32,
+    pub obstacles: Vec<Obstacle>,
+    pub map_path: Option<PathBuf>, //obstacles from a map file instead
+    pub num_neurons: usize,
+    pub num_brains: usize,
+    pub num_sensors: usize,
+    pub sensor_length: f32,
+    pub max_loop_knt: usize,
+    pub fitness_straight: f32, //weights of the fitness components
+    pub fitness_floreano: f32,
+    pub fitness_survival: f32,
+    pub fitness_coverage: f32,
+    pub fitness_clearance: f32,
+    pub coverage_cell: f32, //side of a coverage grid cell
+    pub trials: usize,      //lives per brain before it is scored
+    pub start: StartKind,
+    pub start_positions: Vec<[f32; 2]>, //start = "fixed", taken in turn
+    pub start_headings: Vec<usize>,     //0..7 in turn, empty for random
+    pub aggregate: AggregateKind,
+    pub settling_time: usize, //network passes per think
+    pub threshold: i32,       //firing threshold before noise
+    pub threshold_noise: i32, //noise is uniform in -noise..=noise
+    pub leaking_constant: u8,
+    pub refractory_period: u8, //steps a neuron sits out after firing
+    pub stateful: bool,        //keep membranes between ticks of a life
+    pub wiring: WiringKind,
+    pub neuron_model: NeuronKind,
+    pub lif_tau: f32, //membrane time constant, in network passes
+    pub lif_v_rest: f32,
+    pub lif_v_reset: f32,
+    pub lif_threshold: f32,
+    pub lif_refractory_period: u32,
+    pub lif_input_weight: f32,
+    pub lif_recurrent_weight: f32,
+    pub izh_input_current: f32, //per active, connected input line
+    pub izh_recurrent_current: f32,
+    pub weighted: bool,  //real valued synapses on top of the connection bits
+    pub weight_max: f32, //weights live in 0..=weight_max
+    pub weight_sigma: f32,
+    pub weight_reset_rate: f64, //chance a weight mutation redraws the weight
+    pub ga: GaKind,
+    pub selection: SelectionKind,
+    pub tournament_size: usize,
+    pub rank_pressure: f32, //1 = no pressure, 2 = the most
+    pub truncation: f32,    //fraction of the population allowed to breed
+    pub crossover: CrossoverKind,
+    pub crossover_rate: f64,                //chance a child gets two parents
+    pub elitism: usize,                     //best brains copied unchanged into the next generation
+    pub hall_of_fame: usize,                //best brains ever seen that are kept
+    pub hall_of_fame_path: Option<PathBuf>, //written whenever the hall changes
+    pub checkpoint_every: usize,            //generations between checkpoints, 0 = never
+    pub checkpoint_path: Option<PathBuf>,
+    pub metrics_path: Option<PathBuf>, //a row per life and generation, .csv or .jsonl
+    pub pareto_path: Option<PathBuf>,  //pareto front of every generation
+    pub xsign_mutations: usize,        //bit flips per life
+    pub nconn_mutations: usize,
+    pub iconn_mutations: usize,
+    pub izh_mutations: usize, //gaussian a, b, c, d nudges per life
+    pub weight_mutations: usize,
+    pub mutation: MutationKind,
+    pub xsign_rate: f64, //chance per gene, mutation = "rate"
+    pub nconn_rate: f64,
+    pub iconn_rate: f64,
+    pub izh_rate: f64,
+    pub weight_rate: f64,
+    pub adapt_factor: f64, //below 1 turns on the 1/5th rule
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        SimConfig {
+            width: crate::WIDTH,
+            height: crate::HEIGHT,
+            obstacles: default_obstacles(),
+            map_path: None,
+            num_neurons: crate::NUM_NEURONS,
+            num_brains: crate::NUM_BRAINS,
+            num_sensors: crate::NUM_SENSORS,
+            sensor_length: crate::SENSOR_LENGTH,
+            max_loop_knt: crate::MAX_LOOP_KNT,
+            fitness_straight: 1.0,
+            fitness_floreano: 0.0,
+            fitness_survival: 0.0,
+            fitness_coverage: 0.0,
+            fitness_clearance: 0.0,
+            coverage_cell: 20.0,
+            trials: 1,
+            start: StartKind::Fixed,
+            start_positions: Vec::new(),
+            start_headings: Vec::new(),
+            aggregate: AggregateKind::Mean,
+            settling_time: 20,
+            threshold: 3,
+            threshold_noise: 2,
+            leaking_constant: 1,
+            refractory_period: 1,
+            stateful: false,
+            wiring: WiringKind::Legacy,
+            neuron_model: NeuronKind::Binary,
+            lif_tau: 5.0,
+            lif_v_rest: 0.0,
+            lif_v_reset: 0.0,
+            lif_threshold: 1.0,
+            lif_refractory_period: 1,
+            lif_input_weight: 0.3,
+            lif_recurrent_weight: 0.2,
+            izh_input_current: 10.0,
+            izh_recurrent_current: 5.0,
+            weighted: false,
+            weight_max: 3.0,
+            weight_sigma: 0.3,
+            weight_reset_rate: 0.1,
+            ga: GaKind::Steady,
+            selection: SelectionKind::Roulette,
+            tournament_size: 3,
+            rank_pressure: 1.5,
+            truncation: 0.5,
+            crossover: CrossoverKind::None,
+            crossover_rate: 0.7,
+            elitism: 0,
+            hall_of_fame: 10,
+            hall_of_fame_path: None,
+            checkpoint_every: 0,
+            checkpoint_path: None,
+            metrics_path: None,
+            pareto_path: None,
+            xsign_mutations: 1,
+            nconn_mutations: 1,
+            iconn_mutations: 0,
+            izh_mutations: 1,
+            weight_mutations: 2,
+            mutation: MutationKind::Count,
+            xsign_rate: 0.05,
+            nconn_rate: 0.02,
+            iconn_rate: 0.0,
+            izh_rate: 0.05,
+            weight_rate: 0.02,
+            adapt_factor: 1.0,
+        }
+    }
+}
+
+impl SimConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("can't read {}: {}", path.display(), err))?;
+        let config: SimConfig = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&text).map_err(|err| err.to_string()),
+            _ => toml::from_str(&text).map_err(|err| err.to_string()),
+        }
+        .map_err(|err| format!("bad config {}: {}", path.display(), err))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    //replace the obstacles with those of map_path, if there is one.
+    //a map is TOML or JSON like a config, with only obstacles in it
+    pub fn load_map(&mut self) -> Result<(), String> {
+        let path = match &self.map_path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("can't read {}: {}", path.display(), err))?;
+        let map: Map = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&text).map_err(|err| err.to_string()),
+            _ => toml::from_str(&text).map_err(|err| err.to_string()),
+        }
+        .map_err(|err| format!("bad map {}: {}", path.display(), err))?;
+        self.obstacles = map.obstacles;
+        self.validate()
+    }
+
+    //key=value, e.g. num_brains=20 or sensor_length=45.5
+    //or neuron_model=lif / izhikevich
+    pub fn set(&mut self, key_value: &str) -> Result<(), String> {
+        self.set_all(&[key_value])
+    }
+
+    //several key=value at once, checked together at the end so
+    //keys that depend on each other can come in any order
+    pub fn set_all<S: AsRef<str>>(&mut self, key_values: &[S]) -> Result<(), String> {
+        let mut config = self.clone();
+        for key_value in key_values {
+            config.set_unchecked(key_value.as_ref())?;
+        }
+        config.validate()?;
+        *self = config;
+        Ok(())
+    }
+
+    fn set_unchecked(&mut self, key_value: &str) -> Result<(), String> {
+        let (key, value) = key_value
+            .split_once('=')
+            .ok_or_else(|| format!("override must look like key=value: {}", key_value))?;
+        let mut fields = serde_json::to_value(&*self).map_err(|err| err.to_string())?;
+        let field = fields
+            .get_mut(key.trim())
+            .ok_or_else(|| format!("unknown config key: {}", key))?;
+        //anything that isn't a number, bool ... is taken as a string
+        *field = serde_json::from_str(value.trim())
+            .unwrap_or_else(|_| serde_json::Value::String(value.trim().to_string()));
+        let config: SimConfig = serde_json::from_value(fields)
+            .map_err(|err| format!("bad value for {}: {}", key, err))?;
+        *self = config;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.width > 0.0 && self.height > 0.0 && (self.width * self.height).is_finite()) {
+            return Err("width and height must be positive".to_string());
+        }
+        for obstacle in &self.obstacles {
+            check_obstacle(obstacle)?;
+        }
+        if !self.has_clear_spot() {
+            return Err("obstacles leave no clear spot in the arena to start from".to_string());
+        }
+        if !positive(self.sensor_length) {
+            return Err("sensor_length must be positive".to_string());
+        }
+        if self.num_brains == 0 {
+            return Err("num_brains must be at least 1".to_string());
+        }
+        let weights = [
+            self.fitness_straight,
+            self.fitness_floreano,
+            self.fitness_survival,
+            self.fitness_coverage,
+            self.fitness_clearance,
+        ];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err("fitness weights must be 0 or more".to_string());
+        }
+        if weights.iter().all(|&w| w == 0.0) {
+            return Err("at least one fitness weight must be above 0".to_string());
+        }
+        if !positive(self.coverage_cell) {
+            return Err("coverage_cell must be positive".to_string());
+        }
+        if self.trials == 0 {
+            return Err("trials must be at least 1".to_string());
+        }
+        let (w, h) = (self.width / 2.0, self.height / 2.0);
+        if self
+            .start_positions
+            .iter()
+            .any(|&[x, y]| !(-w..=w).contains(&x) || !(-h..=h).contains(&y))
+        {
+            return Err("start_positions must be inside the arena".to_string());
+        }
+        if self
+            .start_positions
+            .iter()
+            .any(|&[x, y]| self.obstacles.iter().any(|o| o.contains(x, y)))
+        {
+            return Err("start_positions must not be on an obstacle".to_string());
+        }
+        if self.start_headings.iter().any(|&a| a >= crate::NUM_ANGLES) {
+            return Err(format!(
+                "start_headings must be below {}",
+                crate::NUM_ANGLES
+            ));
+        }
+        if !positive(self.lif_tau) {
+            return Err("lif_tau must be positive".to_string());
+        }
+        let neuron_values = [
+            self.lif_v_rest,
+            self.lif_v_reset,
+            self.lif_threshold,
+            self.lif_input_weight,
+            self.lif_recurrent_weight,
+            self.izh_input_current,
+            self.izh_recurrent_current,
+        ];
+        if neuron_values.iter().any(|v| !v.is_finite()) {
+            return Err("lif_* and izh_* values must be numbers".to_string());
+        }
+        if !positive(self.weight_max) || !positive(self.weight_sigma) {
+            return Err("weight_max and weight_sigma must be positive".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.weight_reset_rate) {
+            return Err("weight_reset_rate must be in 0..=1".to_string());
+        }
+        if self.tournament_size == 0 {
+            return Err("tournament_size must be at least 1".to_string());
+        }
+        if !(1.0..=2.0).contains(&self.rank_pressure) {
+            return Err("rank_pressure must be in 1..=2".to_string());
+        }
+        //written so that NaN fails too
+        if !(self.truncation > 0.0 && self.truncation <= 1.0) {
+            return Err("truncation must be in 0..1, 0 not included".to_string());
+        }
+        if self.checkpoint_every > 0 && self.checkpoint_path.is_none() {
+            return Err("checkpoint_every needs a checkpoint_path".to_string());
+        }
+        if let Some(path) = &self.metrics_path {
+            let ext = path.extension().and_then(|ext| ext.to_str());
+            if ext != Some("csv") && ext != Some("jsonl") {
+                return Err("metrics_path must end in .csv or .jsonl".to_string());
+            }
+        }
+        //the steady GA is the original one: roulette, no crossover,
+        //no elites. rather than ignore these, say so
+        if self.ga == GaKind::Steady {
+            if self.selection != SelectionKind::Roulette {
+                return Err(format!(
+                    "selection = {} needs ga = generational",
+                    format!("{:?}", self.selection).to_lowercase()
+                ));
+            }
+            if self.crossover != CrossoverKind::None {
+                return Err("crossover needs ga = generational".to_string());
+            }
+            if self.elitism > 0 {
+                return Err("elitism needs ga = generational".to_string());
+            }
+        }
+        if self.elitism > self.num_brains {
+            return Err("elitism can't be more than num_brains".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.crossover_rate) {
+            return Err("crossover_rate must be in 0..=1".to_string());
+        }
+        let rates = [
+            self.xsign_rate,
+            self.nconn_rate,
+            self.iconn_rate,
+            self.izh_rate,
+            self.weight_rate,
+        ];
+        if rates.iter().any(|rate| !(0.0..=1.0).contains(rate)) {
+            return Err("mutation rates must be in 0..=1".to_string());
+        }
+        if !(self.adapt_factor > 0.0 && self.adapt_factor <= 1.0) {
+            return Err("adapt_factor must be in 0..1, 0 not included".to_string());
+        }
+        if self.threshold_noise < 0 {
+            return Err("threshold_noise can't be negative".to_string());
+        }
+        //every sensor needs an input line and every motor an output neuron
+        let min_neurons = self.num_sensors.max(crate::NUM_MOTORS);
+        if self.num_neurons < min_neurons {
+            return Err(format!("num_neurons must be at least {}", min_neurons));
+        }
+        //the antennae fan out 45 degrees apart, no two the same way
+        if self.num_sensors == 0 || self.num_sensors >= crate::NUM_ANGLES {
+            return Err(format!("num_sensors must be in 1..{}", crate::NUM_ANGLES));
+        }
+        Ok(())
+    }
+
+    fn has_clear_spot(&self) -> bool {
+        let (w, h) = (
+            self.width / CLEAR_GRID as f32,
+            self.height / CLEAR_GRID as f32,
+        );
+        (0..CLEAR_GRID * CLEAR_GRID).any(|k| {
+            let x = -self.width / 2.0 + ((k % CLEAR_GRID) as f32 + 0.5) * w;
+            let y = -self.height / 2.0 + ((k / CLEAR_GRID) as f32 + 0.5) * h;
+            !self.obstacles.iter().any(|o| o.contains(x, y))
+        })
+    }
+} //end of impl SimConfig
+
+//random and stratified starts look for a clear spot, so there has
+//to be one. the middles of a grid over the arena are a good enough look
+const CLEAR_GRID: usize = 32;
+
+//above 0 and a real number, NaN and inf fail
+fn positive(x: f32) -> bool {
+    x > 0.0 && x.is_finite()
+}
+
+//every number finite, sizes positive and polygons convex
+fn check_obstacle(obstacle: &Obstacle) -> Result<(), String> {
+    let numbers: Vec<f32> = match obstacle {
+        Obstacle::Box { x, y, w, h } => vec![*x, *y, *w, *h],
+        Obstacle::Circle { x, y, r } => vec![*x, *y, *r],
+        Obstacle::Polygon { points } => points.iter().flatten().copied().collect(),
+        Obstacle::Segment { from, to } => vec![from[0], from[1], to[0], to[1]],
+    };
+    let ok = numbers.iter().all(|n| n.is_finite())
+        && match obstacle {
+            Obstacle::Box { w, h, .. } => *w > 0.0 && *h > 0.0,
+            Obstacle::Circle { r, .. } => *r > 0.0,
+            Obstacle::Segment { from, to } => from != to,
+            Obstacle::Polygon { points } => {
+                let n = points.len();
+                //the turn at every corner, all the same way and once
+                //round in all. a star turns the same way but twice
+                let turns: Vec<f32> = (0..n)
+                    .map(|i| {
+                        let [ax, ay] = points[i];
+                        let [bx, by] = points[(i + 1) % n];
+                        let [cx, cy] = points[(i + 2) % n];
+                        let (ux, uy, vx, vy) = (bx - ax, by - ay, cx - bx, cy - by);
+                        (ux * vy - uy * vx).atan2(ux * vx + uy * vy)
+                    })
+                    .collect();
+                let total: f32 = turns.iter().sum();
+                n >= 3
+                    && (turns.iter().all(|&t| t > 0.0) || turns.iter().all(|&t| t < 0.0))
+                    && (total.abs() - 2.0 * std::f32::consts::PI).abs() < 1e-3
+            }
+        };
+    if ok {
+        Ok(())
+    } else {
+        Err(format!(
+            "bad obstacle {:?}: sizes must be positive and polygons convex",
+            obstacle
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_files_and_overrides() {
+        let config: SimConfig = toml::from_str("num_brains = 20\nsettling_time = 5").unwrap();
+        assert_eq!(config.num_brains, 20);
+        assert_eq!(config.settling_time, 5);
+        assert_eq!(config.width, crate::WIDTH);
+
+        let config: SimConfig = serde_json::from_str(r#"{"sensor_length": 45.5}"#).unwrap();
+        assert_eq!(config.sensor_length, 45.5);
+
+        let mut config = SimConfig::default();
+        config.set("max_loop_knt=500").unwrap();
+        assert_eq!(config.max_loop_knt, 500);
+        config.set("num_neurons=64").unwrap();
+        assert_eq!(config.num_neurons, 64);
+        assert!(config.set("num_neurons=2").is_err());
+        config.set("num_sensors=5").unwrap();
+        assert!(config.set("num_sensors=8").is_err());
+        assert!(config.set("num_sensors=0").is_err());
+        assert!(config.set("num_sensors=65").is_err());
+        config.set("num_sensors=3").unwrap();
+        config.set("neuron_model=lif").unwrap();
+        assert_eq!(config.neuron_model, NeuronKind::Lif);
+        config.set("neuron_model=izhikevich").unwrap();
+        assert_eq!(config.neuron_model, NeuronKind::Izhikevich);
+        config.set("wiring=presynaptic").unwrap();
+        assert_eq!(config.wiring, WiringKind::Presynaptic);
+        config.set("ga=generational").unwrap();
+        assert_eq!(config.ga, GaKind::Generational);
+        config.set("selection=sus").unwrap();
+        assert_eq!(config.selection, SelectionKind::Sus);
+        config.set("selection=nsga2").unwrap();
+        assert_eq!(config.selection, SelectionKind::Nsga2);
+        //the steady GA has no use for it, nor for crossover or elites
+        assert!(config.set("ga=steady").is_err());
+        let steady = SimConfig::default();
+        assert!(steady.clone().set("selection=tournament").is_err());
+        assert!(steady.clone().set("crossover=uniform").is_err());
+        assert!(steady.clone().set("elitism=2").is_err());
+        assert!(steady
+            .clone()
+            .set_all(&["elitism=2", "ga=generational"])
+            .is_ok());
+        assert!(config.set("rank_pressure=2.5").is_err());
+        //NaN gets past a <= 0 check, it mustn't get past validate
+        let nan = |f: fn(&mut SimConfig)| {
+            let mut config = SimConfig::default();
+            f(&mut config);
+            config.validate().is_err()
+        };
+        assert!(nan(|c| c.weight_max = f32::NAN));
+        assert!(nan(|c| c.weight_sigma = f32::INFINITY));
+        assert!(nan(|c| c.sensor_length = f32::NAN));
+        assert!(nan(|c| c.coverage_cell = f32::NAN));
+        assert!(nan(|c| c.lif_tau = f32::NAN));
+        assert!(nan(|c| c.lif_threshold = f32::NAN));
+        assert!(nan(|c| c.truncation = f32::NAN));
+        assert!(nan(|c| c.adapt_factor = f64::NAN));
+        config.set("crossover=twopoint").unwrap();
+        assert_eq!(config.crossover, CrossoverKind::TwoPoint);
+        config.set("mutation=rate").unwrap();
+        assert_eq!(config.mutation, MutationKind::Rate);
+        config.set("hall_of_fame_path=runs/hof.txt").unwrap();
+        assert_eq!(
+            config.hall_of_fame_path,
+            Some(PathBuf::from("runs/hof.txt"))
+        );
+        config.set("metrics_path=runs/metrics.jsonl").unwrap();
+        assert!(config.set("metrics_path=runs/metrics.txt").is_err());
+        config
+            .set("start_positions=[[150, 150], [-150, -150.5]]")
+            .unwrap();
+        assert_eq!(config.start_positions[1], [-150.0, -150.5]);
+        assert!(config.set("start_positions=[[500, 0]]").is_err());
+        assert!(config.set("start_headings=[8]").is_err());
+        config.set("aggregate=median").unwrap();
+        assert_eq!(config.aggregate, AggregateKind::Median);
+        assert!(config.set("fitness_straight=0").is_err());
+        config
+            .set_all(&["fitness_straight=0", "fitness_floreano=1"])
+            .unwrap();
+        assert!(config.set("iconn_rate=-0.1").is_err());
+        assert!(config.set("neuron_model=hodgkin").is_err());
+        assert!(config.set("num_brains=0").is_err());
+        assert!(config.set("no_such_knob=1").is_err());
+        assert!(config.set("width=wide").is_err());
+        assert_eq!(config.num_brains, crate::NUM_BRAINS);
+
+        //one at a time the first is rejected, together they are fine
+        assert!(config.set("checkpoint_every=5").is_err());
+        config
+            .set_all(&["checkpoint_every=5", "checkpoint_path=run.ckpt"])
+            .unwrap();
+        assert_eq!(config.checkpoint_every, 5);
+        assert!(config.set_all(&["num_brains=4", "num_brains=0"]).is_err());
+        assert_eq!(config.num_brains, crate::NUM_BRAINS);
+    }
+
+    #[test]
+    fn obstacles_inline_and_from_a_map() {
+        let text = r#"
+            [[obstacles]]
+            shape = "circle"
+            x = 100.0
+            y = -100.0
+            r = 25.0
+
+            [[obstacles]]
+            shape = "polygon"
+            points = [[0.0, 0.0], [50.0, 0.0], [0.0, 50.0]]
+
+            [[obstacles]]
+            shape = "segment"
+            from = [-150.0, 0.0]
+            to = [-150.0, 120.0]
+        "#;
+        let config: SimConfig = toml::from_str(text).unwrap();
+        assert_eq!(config.obstacles.len(), 3);
+        assert!(config.validate().is_ok());
+        assert_eq!(SimConfig::default().obstacles, default_obstacles());
+
+        let path = std::env::temp_dir().join(format!("map_{}.toml", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let mut config = SimConfig::default();
+        config.set(&format!("map_path={}", path.display())).unwrap();
+        config.load_map().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            config.obstacles[0],
+            Obstacle::Circle {
+                x: 100.0,
+                y: -100.0,
+                r: 25.0
+            }
+        );
+
+        config.set("obstacles=[]").unwrap();
+        assert!(config.obstacles.is_empty());
+        //a bow tie is not convex
+        let bow_tie =
+            r#"obstacles=[{"shape": "polygon", "points": [[0, 0], [10, 10], [10, 0], [0, 10]]}]"#;
+        assert!(config.set(bow_tie).is_err());
+        //every turn of a pentagram goes the same way, twice round
+        let star: Vec<[f32; 2]> = (0..5)
+            .map(|k| {
+                let a = (k * 2 % 5) as f32 * 2.0 * std::f32::consts::PI / 5.0;
+                [50.0 * a.cos(), 50.0 * a.sin()]
+            })
+            .collect();
+        let mut starred = config.clone();
+        starred.obstacles = vec![Obstacle::Polygon { points: star }];
+        assert!(starred.validate().is_err());
+        starred.obstacles = vec![Obstacle::Circle {
+            x: f32::NAN,
+            y: 0.0,
+            r: 5.0,
+        }];
+        assert!(starred.validate().is_err());
+        starred.obstacles.clear();
+        starred.width = f32::NAN;
+        assert!(starred.validate().is_err());
+        starred.width = f32::INFINITY;
+        assert!(starred.validate().is_err());
+        assert!(config
+            .set(r#"obstacles=[{"shape": "circle", "x": 0, "y": 0, "r": 0}]"#)
+            .is_err());
+        //nowhere left to start
+        assert!(config
+            .set(r#"obstacles=[{"shape": "box", "x": 0, "y": 0, "w": 400, "h": 400}]"#)
+            .is_err());
+        //nor on a rock
+        config.set("start_positions=[[0, 0]]").unwrap();
+        assert!(config
+            .set(r#"obstacles=[{"shape": "circle", "x": 0, "y": 0, "r": 5}]"#)
+            .is_err());
+    }
+}
@@ -3,11 +3,18 @@ pub const HEIGHT: f32 = 400.0; //screen
 pub const NUM_NEURONS: usize = 8; //should be powers of 2
 pub const NUM_BRAINS: usize = 10; //number of brains  -- in ga talk population
 pub const NUM_ANGLES: usize = 8; //rover has 8 possible directions it can travel
-                             //e,ne,n,nw,w,sw,s,se -- kind of like the unit circle in trig
+                                 //e,ne,n,nw,w,sw,s,se -- kind of like the unit circle in trig
 pub const ANGLES_DX: [f32; 8] = [1.0, 1.0, 0.0, -1.0, -1.0, -1.0, 0.0, 1.0];
 pub const ANGLES_DY: [f32; 8] = [0.0, 1.0, 1.0, 1.0, 0.0, -1.0, -1.0, -1.0];
 pub const NUM_SENSORS: usize = 3; //number of antennae
 pub const SENSOR_LENGTH: f32 = 60.0; //length of an antenna
 pub const MAX_LOOP_KNT: usize = 2000; //can't let them live forever
+pub const NUM_MOTORS: usize = 3; //brain outputs: turn left, go straight, turn right
+pub const SEGMENT_WIDTH: f32 = 2.0; //a segment obstacle is this thick to the rover
 
-
+//the consts above are the defaults, a run can change them
+mod config;
+pub use config::{
+    default_obstacles, AggregateKind, CrossoverKind, GaKind, MutationKind, NeuronKind, Obstacle,
+    SelectionKind, SimConfig, StartKind, WiringKind,
+};
@@ -0,0 +1,238 @@
+//Mixing two parents into a child.
+//
+//A genome is the list of genes
+//  xsign[n]     for every neuron, with its izh genes
+//  iconn[n][i]  row by row, with iweight[n][i]
+//  nconn[n][m]  row by row, with nweight[n][m]
+//and a child takes each gene whole from one parent or the other.
+//Parents of different sizes can't be mixed, the child is then a
+//copy of the first one.
+
+use brain::Brain;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub trait Crossover {
+    fn crossover<R: Rng + ?Sized>(&self, a: &Brain, b: &Brain, rng: &mut R) -> Brain;
+}
+
+fn same_shape(a: &Brain, b: &Brain) -> bool {
+    a.num_neurons == b.num_neurons
+        && a.num_inputs == b.num_inputs
+        && a.num_outputs == b.num_outputs
+        && a.izh.len() == b.izh.len()
+        && a.is_weighted() == b.is_weighted()
+}
+
+fn genome_len(brain: &Brain) -> usize {
+    brain.num_neurons * (1 + brain.num_inputs + brain.num_neurons)
+}
+
+//copy gene g (see above) from parent into child
+fn copy_gene(child: &mut Brain, parent: &Brain, g: usize) {
+    let n = parent.num_neurons;
+    let inputs = parent.num_inputs;
+    if g < n {
+        child.xsign[g] = parent.xsign[g];
+        if !parent.izh.is_empty() {
+            child.izh[g] = parent.izh[g];
+        }
+    } else if g < n + n * inputs {
+        let (row, col) = ((g - n) / inputs, (g - n) % inputs);
+        child.iconn[row][col] = parent.iconn[row][col];
+        if parent.is_weighted() {
+            child.iweight[row][col] = parent.iweight[row][col];
+        }
+    } else {
+        let (row, col) = ((g - n - n * inputs) / n, (g - n - n * inputs) % n);
+        child.nconn[row][col] = parent.nconn[row][col];
+        if parent.is_weighted() {
+            child.nweight[row][col] = parent.nweight[row][col];
+        }
+    }
+}
+
+//a copy of a with the genes from_b(g) says taken from b
+fn mix<F: FnMut(usize) -> bool>(a: &Brain, b: &Brain, mut from_b: F) -> Brain {
+    let mut child = a.clone();
+    if !same_shape(a, b) {
+        return child;
+    }
+    for g in 0..genome_len(a) {
+        if from_b(g) {
+            copy_gene(&mut child, b, g);
+        }
+    }
+    child
+}
+
+//asexual: the child is a copy of the first parent
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoCrossover;
+
+impl Crossover for NoCrossover {
+    fn crossover<R: Rng + ?Sized>(&self, a: &Brain, _b: &Brain, _rng: &mut R) -> Brain {
+        a.clone()
+    }
+}
+
+//every gene from either parent with even odds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UniformCrossover;
+
+impl Crossover for UniformCrossover {
+    fn crossover<R: Rng + ?Sized>(&self, a: &Brain, b: &Brain, rng: &mut R) -> Brain {
+        mix(a, b, |_| rng.gen_bool(0.5))
+    }
+}
+
+//every neuron from either parent, with its sign, its incoming
+//input and recurrent links and its izh genes kept together
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NeuronCrossover;
+
+impl Crossover for NeuronCrossover {
+    fn crossover<R: Rng + ?Sized>(&self, a: &Brain, b: &Brain, rng: &mut R) -> Brain {
+        let mut child = a.clone();
+        if !same_shape(a, b) {
+            return child;
+        }
+        for nindex in 0..a.num_neurons {
+            if rng.gen_bool(0.5) {
+                child.xsign[nindex] = b.xsign[nindex];
+                child.iconn[nindex] = b.iconn[nindex].clone();
+                child.nconn[nindex] = b.nconn[nindex].clone();
+                if !b.izh.is_empty() {
+                    child.izh[nindex] = b.izh[nindex];
+                }
+                if b.is_weighted() {
+                    child.iweight[nindex] = b.iweight[nindex].clone();
+                    child.nweight[nindex] = b.nweight[nindex].clone();
+                }
+            }
+        }
+        child
+    }
+}
+
+//genes before a random cut from a, the rest from b
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct OnePointCrossover;
+
+impl Crossover for OnePointCrossover {
+    fn crossover<R: Rng + ?Sized>(&self, a: &Brain, b: &Brain, rng: &mut R) -> Brain {
+        let cut = rng.gen_range(0..=genome_len(a));
+        mix(a, b, |g| g >= cut)
+    }
+}
+
+//genes between two random cuts from b, the rest from a
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TwoPointCrossover;
+
+impl Crossover for TwoPointCrossover {
+    fn crossover<R: Rng + ?Sized>(&self, a: &Brain, b: &Brain, rng: &mut R) -> Brain {
+        let len = genome_len(a);
+        let first = rng.gen_range(0..=len);
+        let second = rng.gen_range(0..=len);
+        let (low, high) = (first.min(second), first.max(second));
+        mix(a, b, |g| low <= g && g < high)
+    }
+}
+
+//an operator picked at run time
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CrossoverMethod {
+    None(NoCrossover),
+    Uniform(UniformCrossover),
+    Neuron(NeuronCrossover),
+    OnePoint(OnePointCrossover),
+    TwoPoint(TwoPointCrossover),
+}
+
+impl Default for CrossoverMethod {
+    fn default() -> Self {
+        CrossoverMethod::None(NoCrossover)
+    }
+}
+
+impl Crossover for CrossoverMethod {
+    fn crossover<R: Rng + ?Sized>(&self, a: &Brain, b: &Brain, rng: &mut R) -> Brain {
+        match self {
+            CrossoverMethod::None(method) => method.crossover(a, b, rng),
+            CrossoverMethod::Uniform(method) => method.crossover(a, b, rng),
+            CrossoverMethod::Neuron(method) => method.crossover(a, b, rng),
+            CrossoverMethod::OnePoint(method) => method.crossover(a, b, rng),
+            CrossoverMethod::TwoPoint(method) => method.crossover(a, b, rng),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    //a is all zeros and b all ones, so every gene shows its parent
+    fn parents() -> (Brain, Brain) {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut a = Brain::random(6, 4, 3, &mut rng).unwrap();
+        a.xsign = vec![0; 6];
+        a.iconn = vec![vec![0; 4]; 6];
+        a.nconn = vec![vec![0; 6]; 6];
+        let mut b = a.clone();
+        b.xsign = vec![1; 6];
+        b.iconn = vec![vec![1; 4]; 6];
+        b.nconn = vec![vec![1; 6]; 6];
+        (a, b)
+    }
+
+    fn genes(brain: &Brain) -> Vec<u8> {
+        let mut genes = brain.xsign.clone();
+        genes.extend(brain.iconn.iter().flatten());
+        genes.extend(brain.nconn.iter().flatten());
+        genes
+    }
+
+    #[test]
+    fn children_mix_both_parents() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        let (a, b) = parents();
+        let methods = [
+            CrossoverMethod::Uniform(UniformCrossover),
+            CrossoverMethod::Neuron(NeuronCrossover),
+            CrossoverMethod::OnePoint(OnePointCrossover),
+            CrossoverMethod::TwoPoint(TwoPointCrossover),
+        ];
+        for method in methods.iter() {
+            let mut from_b = 0;
+            for _ in 0..20 {
+                let child = method.crossover(&a, &b, &mut rng);
+                assert!(child.validate().is_ok());
+                from_b += genes(&child).iter().filter(|&&g| g == 1).count();
+            }
+            assert!(from_b > 0 && from_b < 20 * genes(&a).len(), "{:?}", method);
+        }
+        let child = NoCrossover.crossover(&a, &b, &mut rng);
+        assert_eq!(genes(&child), genes(&a));
+    }
+
+    #[test]
+    fn neuron_and_point_crossover_keep_genes_together() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let (a, b) = parents();
+        for _ in 0..20 {
+            let child = NeuronCrossover.crossover(&a, &b, &mut rng);
+            for nindex in 0..6 {
+                let row = child.xsign[nindex];
+                assert!(child.iconn[nindex].iter().all(|&g| g == row));
+                assert!(child.nconn[nindex].iter().all(|&g| g == row));
+            }
+
+            //one point: a run of a's genes, then a run of b's
+            let child = OnePointCrossover.crossover(&a, &b, &mut rng);
+            let genes = genes(&child);
+            assert!(genes.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//The best brains ever seen.
+//
+//A population only holds the current generation, so a champion
+//that has a bad life (or loses out in selection) is gone for good.
+//The hall of fame keeps a copy of the N best, each genome once.
+
+use crate::selection::compare;
+use brain::Brain;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HallOfFameEntry {
+    pub brain: Brain,
+    pub fitness: f32,
+    pub generation: usize, //or life, with the steady GA
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HallOfFame {
+    pub capacity: usize,
+    pub entries: Vec<HallOfFameEntry>, //best first
+}
+
+impl HallOfFame {
+    pub fn new(capacity: usize) -> Self {
+        HallOfFame {
+            capacity,
+            entries: Vec::new(),
+        }
+    }
+
+    //keep the best capacity entries from now on
+    pub fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries.truncate(capacity);
+    }
+
+    pub fn best(&self) -> Option<&HallOfFameEntry> {
+        self.entries.first()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    //keep brain if it makes the cut. a genome already in the hall
+    //only moves up if it scored better this time.
+    //returns true if the hall changed
+    pub fn offer(&mut self, brain: &Brain, generation: usize) -> bool {
+        let fitness = brain.fitness;
+        if self.capacity == 0 || fitness.is_nan() {
+            return false;
+        }
+        if let Some(ix) = self.entries.iter().position(|e| e.brain.same_genome(brain)) {
+            if compare(fitness, self.entries[ix].fitness) != Ordering::Greater {
+                return false;
+            }
+            self.entries.remove(ix);
+        } else if self.entries.len() == self.capacity {
+            let worst = self.entries[self.entries.len() - 1].fitness;
+            if compare(fitness, worst) != Ordering::Greater {
+                return false;
+            }
+            self.entries.pop();
+        }
+        //after any equal scores, so older entries keep their place
+        let at = self
+            .entries
+            .iter()
+            .position(|e| compare(fitness, e.fitness) == Ordering::Greater)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(
+            at,
+            HallOfFameEntry {
+                brain: brain.clone(),
+                fitness,
+                generation,
+            },
+        );
+        true
+    } //end of offer
+
+    //one line per entry, best first: rank, generation, fitness and
+    //the genome bits. rows of a matrix are separated by '/'
+    pub fn export<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let mut text = String::from("#rank generation fitness xsign iconn nconn\n");
+        for (rank, entry) in self.entries.iter().enumerate() {
+            writeln!(
+                text,
+                "{} {} {} {}",
+                rank,
+                entry.generation,
+                entry.fitness,
+                genome_text(&entry.brain)
+            )
+            .expect("writing to a String");
+        }
+        let path = path.as_ref();
+        fs::write(path, text).map_err(|err| format!("can't write {}: {}", path.display(), err))
+    }
+}
+
+//xsign, iconn and nconn as 0s and 1s, rows separated by '/'
+pub(crate) fn genome_text(brain: &Brain) -> String {
+    let bits = |row: &[u8]| row.iter().map(|b| b.to_string()).collect::<String>();
+    let rows = |m: &[Vec<u8>]| m.iter().map(|row| bits(row)).collect::<Vec<_>>().join("/");
+    format!(
+        "{} {} {}",
+        bits(&brain.xsign),
+        rows(&brain.iconn),
+        rows(&brain.nconn)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn keeps_the_best_genomes_once() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut hof = HallOfFame::new(3);
+        let mut brains: Vec<Brain> = (0..5)
+            .map(|i| {
+                let mut brain = Brain::random(8, 8, 3, &mut rng).unwrap();
+                brain.fitness = i as f32;
+                brain
+            })
+            .collect();
+        for (generation, brain) in brains.iter().enumerate() {
+            hof.offer(brain, generation);
+        }
+        let fitness: Vec<f32> = hof.entries.iter().map(|e| e.fitness).collect();
+        assert_eq!(fitness, vec![4.0, 3.0, 2.0]);
+        assert_eq!(hof.best().unwrap().generation, 4);
+
+        //the same genome again: only a better score counts
+        brains[2].fitness = 1.0;
+        assert!(!hof.offer(&brains[2], 7));
+        brains[2].fitness = 10.0;
+        assert!(hof.offer(&brains[2], 7));
+        assert_eq!(hof.len(), 3);
+        assert_eq!(hof.best().unwrap().generation, 7);
+
+        brains[0].fitness = f32::NAN;
+        assert!(!hof.offer(&brains[0], 8));
+
+        let path = std::env::temp_dir().join(format!("hof_{}.txt", std::process::id()));
+        hof.export(&path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(text.lines().count(), 1 + 3);
+        assert!(text.lines().nth(1).unwrap().starts_with("0 7 10 "));
+    }
+}
@@ -0,0 +1,330 @@
+//Generational evolution of Brains.
+//
+//Every brain of a generation is evaluated, then the next generation
+//is bred from it: pick two parents (Selection), mix them (Crossover)
+//and change the child (Mutation). The operators are traits so they
+//can be swapped and tested without a rover, see the Floreano paper
+//for the scheme.
+
+extern crate brain;
+use brain::Brain;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+mod crossover;
+mod hall_of_fame;
+mod mutation;
+mod nsga2;
+mod selection;
+mod store;
+pub use crossover::{
+    Crossover, CrossoverMethod, NeuronCrossover, NoCrossover, OnePointCrossover, TwoPointCrossover,
+    UniformCrossover,
+};
+pub use hall_of_fame::{HallOfFame, HallOfFameEntry};
+pub use mutation::{Adaptation, FlipMutation, Mutation, MutationMethod, RateMutation};
+pub use nsga2::{crowding_distance, dominates, export_front, fronts, pareto_front, Nsga2};
+pub use selection::{
+    compare, LinearRank, Roulette, Selection, SelectionMethod, StochasticUniversal, Tournament,
+    Truncation,
+};
+pub use store::{load, load_brain, load_population, save, save_brain, save_population};
+
+//fitness summary of one evaluated generation
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub best: f32,
+    pub mean: f32,
+    pub worst: f32,
+    pub std_dev: f32,
+}
+
+impl GenerationStats {
+    pub fn new(generation: usize, fitness: &[f32]) -> Self {
+        if fitness.is_empty() {
+            return GenerationStats {
+                generation,
+                ..GenerationStats::default()
+            };
+        }
+        let n = fitness.len() as f32;
+        let mean = fitness.iter().sum::<f32>() / n;
+        let var = fitness.iter().map(|f| (f - mean) * (f - mean)).sum::<f32>() / n;
+        GenerationStats {
+            generation,
+            best: fitness.iter().copied().fold(f32::MIN, f32::max),
+            mean,
+            worst: fitness.iter().copied().fold(f32::MAX, f32::min),
+            std_dev: var.sqrt(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Population {
+    pub brains: Vec<Brain>,
+    pub generation: usize,
+    pub history: Vec<GenerationStats>, //one entry per bred generation
+    pub parent_fitness: Vec<f32>,      //of each brain's first parent, empty at first
+    #[serde(default)]
+    pub parents: Vec<Brain>, //what the brains were bred from, if Selection::survive keeps it
+    pub hall_of_fame: HallOfFame,
+    pub lives: usize, //lived so far, by any brain
+    #[serde(default)]
+    pub evaluations: usize, //brains scored so far, each over all its trials
+    #[serde(default)]
+    pub adaptation: Adaptation, //of the mutation rates, filled in when saved
+}
+
+impl Population {
+    pub fn new(brains: Vec<Brain>) -> Self {
+        Population {
+            brains,
+            generation: 0,
+            history: Vec::new(),
+            parent_fitness: Vec::new(),
+            parents: Vec::new(),
+            hall_of_fame: HallOfFame::new(10),
+            lives: 0,
+            evaluations: 0,
+            adaptation: Adaptation::default(),
+        }
+    }
+
+    //keep the n best brains ever seen instead of 10
+    pub fn with_hall_of_fame(mut self, n: usize) -> Self {
+        self.hall_of_fame = HallOfFame::new(n);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.brains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.brains.is_empty()
+    }
+
+    pub fn fitness(&self) -> Vec<f32> {
+        self.brains.iter().map(|brain| brain.fitness).collect()
+    }
+
+    pub fn stats(&self) -> GenerationStats {
+        GenerationStats::new(self.generation, &self.fitness())
+    }
+
+    //mean distance between every two brains, 0 when they are all
+    //the same genome
+    pub fn diversity(&self) -> f32 {
+        let n = self.brains.len();
+        if n < 2 {
+            return 0.0;
+        }
+        let mut sum = 0.0;
+        for (i, a) in self.brains.iter().enumerate() {
+            for b in &self.brains[i + 1..] {
+                sum += a.distance(b);
+            }
+        }
+        sum / (n * (n - 1) / 2) as f32
+    }
+
+    //score every brain of the generation
+    pub fn evaluate<F: FnMut(&Brain) -> f32>(&mut self, mut fitness_of: F) {
+        for brain in self.brains.iter_mut() {
+            brain.fitness = fitness_of(brain);
+        }
+    }
+}
+
+//the generational GA: a whole new population every generation
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Generational<S, C, M> {
+    pub selection: S,
+    pub crossover: C,
+    pub mutation: M,
+    pub crossover_rate: f64, //otherwise the child is a copy of its first parent
+    pub elitism: usize,      //the best k go on unchanged
+}
+
+impl<S: Selection, C: Crossover, M: Mutation> Generational<S, C, M> {
+    //replace an evaluated population with its children.
+    //the evaluated brains are offered to the hall of fame first.
+    //returns the stats of the generation that was just evaluated
+    pub fn breed<R: Rng + ?Sized>(
+        &mut self,
+        population: &mut Population,
+        rng: &mut R,
+    ) -> GenerationStats {
+        let stats = population.stats();
+        let fitness = population.fitness();
+        for brain in population.brains.iter() {
+            population.hall_of_fame.offer(brain, population.generation);
+        }
+        if population.parent_fitness.len() == fitness.len() {
+            let better = fitness
+                .iter()
+                .zip(&population.parent_fitness)
+                .filter(|(child, parent)| child > parent)
+                .count();
+            self.mutation.adapt(better as f64 / fitness.len() as f64);
+        }
+        //NSGA-II keeps the best of parents and children together
+        let parents = std::mem::take(&mut population.parents);
+        let evaluated = std::mem::take(&mut population.brains);
+        population.brains = self.selection.survive(parents, evaluated);
+        let fitness = population.fitness();
+
+        let mut children = Vec::with_capacity(population.len());
+        let mut parent_fitness = Vec::with_capacity(population.len());
+
+        //elites are lived again, their score starts from 0 too
+        let scores = self.selection.scores(&population.brains);
+        let elites = self.elitism.min(population.len());
+        for &ix in selection::ranked(&scores).iter().rev().take(elites) {
+            let mut elite = population.brains[ix].clone();
+            elite.fitness = 0.0;
+            elite.objectives.clear();
+            children.push(elite);
+            parent_fitness.push(fitness[ix]);
+        }
+
+        //a generation of nothing but elites needs no parents
+        let wanted = 2 * (population.len() - elites);
+        let parents = if wanted > 0 {
+            self.selection.select_many(&scores, wanted, rng)
+        } else {
+            Vec::new()
+        };
+        for pair in parents.chunks(2) {
+            let (a, b) = (&population.brains[pair[0]], &population.brains[pair[1]]);
+            let mut child = if rng.gen_bool(self.crossover_rate) {
+                self.crossover.crossover(a, b, rng)
+            } else {
+                a.clone()
+            };
+            self.mutation.mutate(&mut child, rng);
+            child.fitness = 0.0;
+            child.objectives.clear();
+            children.push(child);
+            parent_fitness.push(a.fitness);
+        }
+        let survivors = std::mem::replace(&mut population.brains, children);
+        if self.selection.keeps_parents() {
+            population.parents = survivors;
+        }
+        population.parent_fitness = parent_fitness;
+        population.history.push(stats.clone());
+        population.generation += 1;
+        stats
+    } //end of breed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn it_works() {
+        assert_eq!(2 + 2, 4);
+    }
+
+    fn population<R: Rng>(size: usize, rng: &mut R) -> Population {
+        let brains = (0..size)
+            .map(|_| Brain::random(8, 8, 3, rng).unwrap())
+            .collect();
+        Population::new(brains)
+    }
+
+    #[test]
+    fn stats_of_a_generation() {
+        let stats = GenerationStats::new(3, &[1.0, 2.0, 3.0, 6.0]);
+        assert_eq!(stats.generation, 3);
+        assert_eq!(stats.best, 6.0);
+        assert_eq!(stats.worst, 1.0);
+        assert_eq!(stats.mean, 3.0);
+        assert!((stats.std_dev - 3.5f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn selection_pressure_raises_fitness() {
+        //fitness grows with the number of excitatory neurons,
+        //selection should push the mean up over a few generations
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut pop = population(30, &mut rng);
+        let mut ga = Generational {
+            selection: Tournament::default(),
+            crossover: UniformCrossover,
+            mutation: FlipMutation::default(),
+            crossover_rate: 0.7,
+            elitism: 0,
+        };
+        let excitatory = |brain: &Brain| {
+            let knt: f32 = brain.xsign.iter().map(|&s| s as f32).sum();
+            knt * knt
+        };
+        for _ in 0..20 {
+            pop.evaluate(excitatory);
+            ga.breed(&mut pop, &mut rng);
+        }
+        pop.evaluate(excitatory);
+        assert_eq!(pop.generation, 20);
+        assert_eq!(pop.history.len(), 20);
+        assert_eq!(pop.len(), 30);
+        assert!(pop.stats().mean > pop.history[0].mean * 1.5);
+        assert!(pop.brains.iter().all(|brain| brain.validate().is_ok()));
+    }
+
+    #[test]
+    fn elites_survive_unchanged() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut pop = population(10, &mut rng).with_hall_of_fame(3);
+        let mut ga = Generational {
+            selection: Roulette,
+            crossover: UniformCrossover,
+            mutation: FlipMutation::default(),
+            crossover_rate: 1.0,
+            elitism: 2,
+        };
+        for (i, brain) in pop.brains.iter_mut().enumerate() {
+            brain.fitness = i as f32;
+        }
+        let best = pop.brains[9].clone();
+        let second = pop.brains[8].clone();
+        ga.breed(&mut pop, &mut rng);
+        assert!(pop.brains[0].same_genome(&best));
+        assert!(pop.brains[1].same_genome(&second));
+        assert_eq!(pop.brains[0].fitness, 0.0);
+        assert_eq!(pop.len(), 10);
+
+        let hof = &pop.hall_of_fame;
+        assert_eq!(hof.len(), 3);
+        assert!(hof.best().unwrap().brain.same_genome(&best));
+        assert_eq!(hof.best().unwrap().fitness, 9.0);
+        assert_eq!(hof.best().unwrap().generation, 0);
+    }
+
+    #[test]
+    fn elitism_can_fill_the_generation() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut pop = population(4, &mut rng);
+        let mut ga = Generational {
+            selection: StochasticUniversal,
+            crossover: UniformCrossover,
+            mutation: FlipMutation::default(),
+            crossover_rate: 0.7,
+            elitism: 4,
+        };
+        for (i, brain) in pop.brains.iter_mut().enumerate() {
+            brain.fitness = i as f32;
+        }
+        let before = pop.brains.clone();
+        ga.breed(&mut pop, &mut rng);
+        assert_eq!(pop.len(), 4);
+        for (brain, old) in pop.brains.iter().zip(before.iter().rev()) {
+            assert!(brain.same_genome(old));
+        }
+    }
+}
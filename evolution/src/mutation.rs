@@ -0,0 +1,297 @@
+//Changing a child.
+
+use brain::{Brain, WeightMutation};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub trait Mutation {
+    fn mutate<R: Rng + ?Sized>(&self, brain: &mut Brain, rng: &mut R);
+
+    //told after every generation what fraction of the children
+    //beat their parent, for operators that tune themselves
+    fn adapt(&mut self, _success: f64) {}
+}
+
+//a fixed number of changes per brain: bit flips in xsign, nconn
+//and iconn, then nudges to the Izhikevich genes and the weights
+//for brains that carry them
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FlipMutation {
+    pub xsign: usize,
+    pub nconn: usize,
+    pub iconn: usize,
+    pub izh: usize,
+    pub weights: usize,
+    pub weight: WeightMutation,
+}
+
+impl Default for FlipMutation {
+    fn default() -> Self {
+        FlipMutation {
+            xsign: 1,
+            nconn: 1,
+            iconn: 0,
+            izh: 1,
+            weights: 2,
+            weight: WeightMutation::default(),
+        }
+    }
+}
+
+impl Mutation for FlipMutation {
+    fn mutate<R: Rng + ?Sized>(&self, brain: &mut Brain, rng: &mut R) {
+        let num_neurons = brain.num_neurons;
+        for _ in 0..self.xsign {
+            let mutidx = rng.gen_range(0..num_neurons);
+            brain.xsign[mutidx] ^= 1;
+        }
+
+        for _ in 0..self.nconn {
+            let mutidx = rng.gen_range(0..num_neurons);
+            let ilink = rng.gen_range(0..num_neurons);
+            brain.nconn[mutidx][ilink] ^= 1;
+        }
+
+        //off by default, the paper keeps all input signals
+        for _ in 0..self.iconn {
+            let mutidx = rng.gen_range(0..num_neurons);
+            let ilink = rng.gen_range(0..brain.num_inputs);
+            brain.iconn[mutidx][ilink] ^= 1;
+        }
+
+        //only brains built for the izhikevich model carry these genes
+        if !brain.izh.is_empty() {
+            for _ in 0..self.izh {
+                let mutidx = rng.gen_range(0..num_neurons);
+                brain.izh[mutidx].mutate(rng);
+            }
+        }
+
+        //and these only when weighted
+        if brain.is_weighted() {
+            for _ in 0..self.weights {
+                brain.mutate_weight(&self.weight, rng);
+            }
+        }
+    } //end of mutate
+}
+
+//every gene changes on its own with its kind's probability.
+//scale multiplies all of them. With adapt_factor below 1 it follows
+//Rechenberg's 1/5th rule: more than a fifth of the children beating
+//their parent means the rates can grow, fewer means shrink them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RateMutation {
+    pub xsign: f64,   //per sign bit
+    pub nconn: f64,   //per recurrent link bit
+    pub iconn: f64,   //per input link bit, 0 keeps all input signals
+    pub izh: f64,     //per neuron, izhikevich brains only
+    pub weights: f64, //per weight, weighted brains only
+    pub weight: WeightMutation,
+    pub scale: f64,
+    pub adapt_factor: f64, //1 = fixed rates
+}
+
+impl Default for RateMutation {
+    fn default() -> Self {
+        RateMutation {
+            xsign: 0.05,
+            nconn: 0.02,
+            iconn: 0.0,
+            izh: 0.05,
+            weights: 0.02,
+            weight: WeightMutation::default(),
+            scale: 1.0,
+            adapt_factor: 1.0,
+        }
+    }
+}
+
+impl RateMutation {
+    fn rate(&self, rate: f64) -> f64 {
+        (rate * self.scale).min(1.0)
+    }
+
+    fn flip_bits<R: Rng + ?Sized>(bits: &mut [u8], rate: f64, rng: &mut R) {
+        if rate <= 0.0 {
+            return;
+        }
+        for bit in bits.iter_mut() {
+            if rng.gen_bool(rate) {
+                *bit ^= 1;
+            }
+        }
+    }
+}
+
+impl Mutation for RateMutation {
+    fn mutate<R: Rng + ?Sized>(&self, brain: &mut Brain, rng: &mut R) {
+        RateMutation::flip_bits(&mut brain.xsign, self.rate(self.xsign), rng);
+        for row in brain.nconn.iter_mut() {
+            RateMutation::flip_bits(row, self.rate(self.nconn), rng);
+        }
+        for row in brain.iconn.iter_mut() {
+            RateMutation::flip_bits(row, self.rate(self.iconn), rng);
+        }
+
+        let rate = self.rate(self.izh);
+        if rate > 0.0 {
+            for params in brain.izh.iter_mut() {
+                if rng.gen_bool(rate) {
+                    params.mutate(rng);
+                }
+            }
+        }
+
+        let rate = self.rate(self.weights);
+        if rate > 0.0 {
+            for weight in brain
+                .iweight
+                .iter_mut()
+                .chain(brain.nweight.iter_mut())
+                .flatten()
+            {
+                if rng.gen_bool(rate) {
+                    self.weight.apply(weight, rng);
+                }
+            }
+        }
+    } //end of mutate
+
+    fn adapt(&mut self, success: f64) {
+        if self.adapt_factor >= 1.0 {
+            return;
+        }
+        if success > 0.2 {
+            self.scale /= self.adapt_factor;
+        } else if success < 0.2 {
+            self.scale *= self.adapt_factor;
+        }
+        self.scale = self.scale.clamp(1e-3, 1e3);
+    }
+}
+
+//an operator picked at run time
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MutationMethod {
+    Count(FlipMutation),
+    Rate(RateMutation),
+}
+
+impl MutationMethod {
+    //what adapt has made of the rates, 1 for fixed counts
+    pub fn scale(&self) -> f64 {
+        match self {
+            MutationMethod::Count(_) => 1.0,
+            MutationMethod::Rate(method) => method.scale,
+        }
+    }
+
+    pub fn set_scale(&mut self, scale: f64) {
+        if let MutationMethod::Rate(method) = self {
+            method.scale = scale;
+        }
+    }
+}
+
+//how far the 1/5th rule has got, saved with a population so a
+//resumed run carries on with it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Adaptation {
+    pub scale: f64,
+    pub children: usize, //lived since the last adapt, steady GA only
+    pub better: usize,   //of them, the ones that beat their parent
+}
+
+impl Default for Adaptation {
+    fn default() -> Self {
+        Adaptation {
+            scale: 1.0,
+            children: 0,
+            better: 0,
+        }
+    }
+}
+
+impl Default for MutationMethod {
+    fn default() -> Self {
+        MutationMethod::Count(FlipMutation::default())
+    }
+}
+
+impl Mutation for MutationMethod {
+    fn mutate<R: Rng + ?Sized>(&self, brain: &mut Brain, rng: &mut R) {
+        match self {
+            MutationMethod::Count(method) => method.mutate(brain, rng),
+            MutationMethod::Rate(method) => method.mutate(brain, rng),
+        }
+    }
+
+    fn adapt(&mut self, success: f64) {
+        match self {
+            MutationMethod::Count(method) => method.adapt(success),
+            MutationMethod::Rate(method) => method.adapt(success),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn flipped(a: &Brain, b: &Brain) -> (usize, usize, usize) {
+        let diff = |x: &[u8], y: &[u8]| x.iter().zip(y).filter(|(p, q)| p != q).count();
+        let rows = |x: &[Vec<u8>], y: &[Vec<u8>]| x.iter().zip(y).map(|(p, q)| diff(p, q)).sum();
+        (
+            diff(&a.xsign, &b.xsign),
+            rows(&a.nconn, &b.nconn),
+            rows(&a.iconn, &b.iconn),
+        )
+    }
+
+    #[test]
+    fn rates_apply_per_gene_kind() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let brain = Brain::random(64, 64, 3, &mut rng).unwrap();
+        let mutation = RateMutation {
+            xsign: 0.0,
+            nconn: 0.1,
+            iconn: 0.0,
+            ..RateMutation::default()
+        };
+        let mut child = brain.clone();
+        mutation.mutate(&mut child, &mut rng);
+        let (xsign, nconn, iconn) = flipped(&brain, &child);
+        assert_eq!((xsign, iconn), (0, 0));
+        //about 410 of 4096
+        assert!(nconn > 300 && nconn < 520, "{}", nconn);
+
+        let mutation = RateMutation {
+            iconn: 1.0,
+            ..mutation
+        };
+        let mut child = brain.clone();
+        mutation.mutate(&mut child, &mut rng);
+        assert!(child.iconn.iter().flatten().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn one_fifth_rule_moves_the_scale() {
+        let mut mutation = RateMutation {
+            adapt_factor: 0.5,
+            ..RateMutation::default()
+        };
+        mutation.adapt(0.5);
+        assert_eq!(mutation.scale, 2.0);
+        mutation.adapt(0.2);
+        assert_eq!(mutation.scale, 2.0);
+        mutation.adapt(0.0);
+        mutation.adapt(0.0);
+        assert_eq!(mutation.scale, 0.5);
+
+        let mut fixed = RateMutation::default();
+        fixed.adapt(1.0);
+        assert_eq!(fixed.scale, 1.0);
+    }
+}
@@ -0,0 +1,335 @@
+//NSGA-II -- parents picked on several objectives at once.
+//
+//Deb, Pratap, Agarwal and Meyarivan, "A fast and elitist
+//multiobjective genetic algorithm: NSGA-II", 2002.
+//
+//A brain dominates another when it is no worse on any objective
+//and better on at least one. The first front is every brain nobody
+//dominates (the Pareto front), the second is what is left once the
+//first is taken away, and so on. Within a front, brains in sparse
+//parts of the trade-off (high crowding distance) come first.
+//
+//Each generation the evaluated children are pooled with the parents
+//they were bred from and the best half of the pool survives, whole
+//fronts first and the last one cut by crowding distance (the
+//elitist (mu + lambda) step). Parents are then picked from the
+//survivors by binary tournament on front and crowding.
+//
+//The objectives are brain.objectives, higher is better, or just the
+//fitness for a brain without any. NaN counts as the worst score.
+
+use crate::hall_of_fame::genome_text;
+use crate::selection::{compare, Selection};
+use brain::Brain;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write as _;
+use std::path::Path;
+
+fn objectives(brain: &Brain) -> Vec<f32> {
+    if brain.objectives.is_empty() {
+        vec![brain.fitness]
+    } else {
+        brain.objectives.clone()
+    }
+}
+
+pub fn dominates(a: &[f32], b: &[f32]) -> bool {
+    let mut better = false;
+    for (&x, &y) in a.iter().zip(b) {
+        match compare(x, y) {
+            Ordering::Less => return false,
+            Ordering::Greater => better = true,
+            Ordering::Equal => {}
+        }
+    }
+    better
+}
+
+//indexes of each front, the Pareto front first
+pub fn fronts(objectives: &[Vec<f32>]) -> Vec<Vec<usize>> {
+    let n = objectives.len();
+    let mut dominated_by = vec![0; n]; //how many dominate i
+    let mut dominates_list = vec![Vec::new(); n]; //who i dominates
+    for i in 0..n {
+        for j in i + 1..n {
+            if dominates(&objectives[i], &objectives[j]) {
+                dominates_list[i].push(j);
+                dominated_by[j] += 1;
+            } else if dominates(&objectives[j], &objectives[i]) {
+                dominates_list[j].push(i);
+                dominated_by[i] += 1;
+            }
+        }
+    }
+    let mut fronts = Vec::new();
+    let mut front: Vec<usize> = (0..n).filter(|&i| dominated_by[i] == 0).collect();
+    while !front.is_empty() {
+        let mut next = Vec::new();
+        for &i in &front {
+            for &j in &dominates_list[i] {
+                dominated_by[j] -= 1;
+                if dominated_by[j] == 0 {
+                    next.push(j);
+                }
+            }
+        }
+        next.sort_unstable();
+        fronts.push(front);
+        front = next;
+    }
+    fronts
+}
+
+//per brain of front, in the same order. the ends of every
+//objective get infinity so they are always kept
+pub fn crowding_distance(objectives: &[Vec<f32>], front: &[usize]) -> Vec<f32> {
+    let mut distance = vec![0.0f32; front.len()];
+    if front.len() < 3 {
+        return vec![f32::INFINITY; front.len()];
+    }
+    //one column per objective, in front order
+    let columns: Vec<Vec<f32>> = (0..objectives[front[0]].len())
+        .map(|m| front.iter().map(|&ix| objectives[ix][m]).collect())
+        .collect();
+    let mut order: Vec<usize> = (0..front.len()).collect();
+    for column in &columns {
+        let value = |k: usize| column[k];
+        order.sort_by(|&a, &b| compare(value(a), value(b)));
+        let (low, high) = (value(order[0]), value(order[front.len() - 1]));
+        distance[order[0]] = f32::INFINITY;
+        distance[order[front.len() - 1]] = f32::INFINITY;
+        let range = high - low;
+        if !range.is_finite() || range <= 0.0 {
+            continue;
+        }
+        for k in 1..front.len() - 1 {
+            let gap = (value(order[k + 1]) - value(order[k - 1])) / range;
+            if gap.is_finite() {
+                distance[order[k]] += gap;
+            }
+        }
+    }
+    distance
+}
+
+//indexes of the brains nobody beats on every objective
+pub fn pareto_front(brains: &[Brain]) -> Vec<usize> {
+    let objectives: Vec<Vec<f32>> = brains.iter().map(objectives).collect();
+    fronts(&objectives).into_iter().next().unwrap_or_default()
+}
+
+//the pareto front of brains, best fitness first, added to the end of
+//path: generation, brain index, fitness, one column per objective
+//(named by names) and the genome. generation 0 starts the file afresh
+pub fn export_front<P: AsRef<Path>>(
+    brains: &[Brain],
+    names: &[&str],
+    generation: usize,
+    path: P,
+) -> Result<(), String> {
+    let path = path.as_ref();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(generation == 0)
+        .append(generation > 0)
+        .truncate(generation == 0)
+        .open(path)
+        .map_err(|err| format!("can't write {}: {}", path.display(), err))?;
+    let empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+    let mut text = String::new();
+    if empty {
+        writeln!(
+            text,
+            "#generation brain fitness {} xsign iconn nconn",
+            names.join(" ")
+        )
+        .expect("writing to a String");
+    }
+    let mut front = pareto_front(brains);
+    front.sort_by(|&a, &b| compare(brains[b].fitness, brains[a].fitness));
+    for ix in front {
+        let brain = &brains[ix];
+        let scores: Vec<String> = objectives(brain).iter().map(|s| s.to_string()).collect();
+        writeln!(
+            text,
+            "{} {} {} {} {}",
+            generation,
+            ix,
+            brain.fitness,
+            scores.join(" "),
+            genome_text(brain)
+        )
+        .expect("writing to a String");
+    }
+    file.write_all(text.as_bytes())
+        .map_err(|err| format!("can't write {}: {}", path.display(), err))
+}
+
+//binary tournament on front, then crowding distance. scores()
+//turns both into one number so elitism keeps the best fronts too.
+//survive() is the (mu + lambda) step
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Nsga2;
+
+impl Selection for Nsga2 {
+    fn select<R: Rng + ?Sized>(&self, fitness: &[f32], rng: &mut R) -> usize {
+        let a = rng.gen_range(0..fitness.len());
+        let b = rng.gen_range(0..fitness.len());
+        if compare(fitness[b], fitness[a]) == Ordering::Greater {
+            b
+        } else {
+            a
+        }
+    }
+
+    //2 per front from the last one up, plus 0..=1 for crowding,
+    //so any brain beats all of a worse front
+    fn scores(&self, brains: &[Brain]) -> Vec<f32> {
+        let objectives: Vec<Vec<f32>> = brains.iter().map(objectives).collect();
+        let fronts = fronts(&objectives);
+        let mut scores = vec![0.0; brains.len()];
+        for (rank, front) in fronts.iter().enumerate() {
+            let level = 2.0 * (fronts.len() - rank) as f32;
+            let distance = crowding_distance(&objectives, front);
+            for (&ix, &d) in front.iter().zip(&distance) {
+                let crowding = if d.is_infinite() { 1.0 } else { d / (1.0 + d) };
+                scores[ix] = level + crowding;
+            }
+        }
+        scores
+    }
+
+    //as many as there are children out of parents and children
+    //together: whole fronts, then the least crowded of the next
+    fn survive(&self, parents: Vec<Brain>, children: Vec<Brain>) -> Vec<Brain> {
+        let n = children.len();
+        let mut pool = parents;
+        pool.extend(children);
+        let objectives: Vec<Vec<f32>> = pool.iter().map(objectives).collect();
+        let mut keep = Vec::with_capacity(n);
+        for front in fronts(&objectives) {
+            if keep.len() + front.len() <= n {
+                keep.extend(front);
+                continue;
+            }
+            let distance = crowding_distance(&objectives, &front);
+            let mut order: Vec<usize> = (0..front.len()).collect();
+            order.sort_by(|&a, &b| compare(distance[b], distance[a]));
+            keep.extend(order.iter().take(n - keep.len()).map(|&k| front[k]));
+            break;
+        }
+        let mut pool: Vec<Option<Brain>> = pool.into_iter().map(Some).collect();
+        keep.iter()
+            .map(|&ix| pool[ix].take().expect("fronts don't repeat a brain"))
+            .collect()
+    }
+
+    fn keeps_parents(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use std::fs;
+
+    #[test]
+    fn fronts_and_crowding() {
+        //0, 1 and 2 trade off, 3 is beaten by 1, 4 by everyone
+        let objectives = vec![
+            vec![1.0, 5.0],
+            vec![3.0, 3.0],
+            vec![5.0, 1.0],
+            vec![2.0, 2.0],
+            vec![0.0, f32::NAN],
+        ];
+        assert!(dominates(&objectives[1], &objectives[3]));
+        assert!(!dominates(&objectives[0], &objectives[2]));
+        assert_eq!(fronts(&objectives), vec![vec![0, 1, 2], vec![3], vec![4]]);
+        let distance = crowding_distance(&objectives, &[0, 1, 2]);
+        assert!(distance[0].is_infinite() && distance[2].is_infinite());
+        assert_eq!(distance[1], 2.0);
+    }
+
+    #[test]
+    fn nsga2_prefers_the_front() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut brains: Vec<Brain> = (0..4)
+            .map(|_| Brain::random(8, 8, 3, &mut rng).unwrap())
+            .collect();
+        brains[0].objectives = vec![1.0, 5.0];
+        brains[1].objectives = vec![5.0, 1.0];
+        brains[2].objectives = vec![0.5, 0.5];
+        brains[3].objectives = vec![4.0, 4.0];
+        assert_eq!(pareto_front(&brains), vec![0, 1, 3]);
+        let scores = Nsga2.scores(&brains);
+        assert!(scores[2] < scores[0] && scores[2] < scores[3]);
+        let picks = Nsga2.select_many(&scores, 1000, &mut rng);
+        let worst = picks.iter().filter(|&&ix| ix == 2).count();
+        //only when it is drawn twice
+        assert!(worst < 1000 / 8, "{}", worst);
+    }
+
+    #[test]
+    fn survivors_come_from_parents_and_children() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        let mut brain = |objectives: Vec<f32>| {
+            let mut brain = Brain::random(8, 8, 3, &mut rng).unwrap();
+            brain.fitness = objectives.iter().sum();
+            brain.objectives = objectives;
+            brain
+        };
+        let parents = vec![brain(vec![5.0, 5.0]), brain(vec![1.0, 1.0])];
+        let children = vec![
+            brain(vec![0.0, 9.0]),
+            brain(vec![2.0, 2.0]),
+            brain(vec![9.0, 0.0]),
+        ];
+        //first front 5,5 0,9 9,0 fills it, the children's 2,2 and
+        //the parent's 1,1 are dropped
+        let survivors = Nsga2.survive(parents, children);
+        let kept: Vec<Vec<f32>> = survivors.iter().map(|b| b.objectives.clone()).collect();
+        assert_eq!(kept, vec![vec![5.0, 5.0], vec![0.0, 9.0], vec![9.0, 0.0]]);
+
+        //a cut front keeps its ends
+        let line: Vec<Brain> = (0..5)
+            .map(|k| brain(vec![k as f32, 4.0 - k as f32]))
+            .collect();
+        let survivors = Nsga2.survive(Vec::new(), line[..3].to_vec());
+        assert_eq!(survivors.len(), 3);
+        let survivors = Nsga2.survive(line[3..].to_vec(), line[..3].to_vec());
+        let kept: Vec<f32> = survivors.iter().map(|b| b.objectives[0]).collect();
+        assert!(kept.contains(&0.0) && kept.contains(&4.0), "{:?}", kept);
+    }
+
+    #[test]
+    fn fronts_are_added_per_generation() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let brains: Vec<Brain> = (0..3)
+            .map(|k| {
+                let mut brain = Brain::random(8, 8, 3, &mut rng).unwrap();
+                brain.objectives = vec![k as f32, 2.0 - k as f32];
+                brain
+            })
+            .collect();
+        let path = std::env::temp_dir().join(format!("front_{}.txt", std::process::id()));
+        for generation in 0..2 {
+            export_front(&brains, &["a", "b"], generation, &path).unwrap();
+        }
+        let text = fs::read_to_string(&path).unwrap();
+        export_front(&brains, &["a", "b"], 0, &path).unwrap();
+        let again = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 1 + 3 + 3);
+        assert!(lines[0].starts_with("#generation brain fitness a b"));
+        assert!(lines[1].starts_with("0 ") && lines[6].starts_with("1 "));
+        assert_eq!(again.lines().count(), 1 + 3);
+    }
+}
@@ -0,0 +1,344 @@
+//Picking parents.
+//
+//Every method copes with any fitness values: NaN counts as the
+//worst possible score, and the fitness proportional ones shift
+//negative scores up and fall back to a uniform pick when nobody
+//scored anything.
+
+use crate::nsga2::Nsga2;
+use brain::Brain;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+pub trait Selection {
+    //index of one parent, given everyone's fitness
+    fn select<R: Rng + ?Sized>(&self, fitness: &[f32], rng: &mut R) -> usize;
+
+    //n parents at once
+    fn select_many<R: Rng + ?Sized>(&self, fitness: &[f32], n: usize, rng: &mut R) -> Vec<usize> {
+        (0..n).map(|_| self.select(fitness, rng)).collect()
+    }
+
+    //what select and elitism go by, the fitness unless the method
+    //looks at more than that
+    fn scores(&self, brains: &[Brain]) -> Vec<f32> {
+        brains.iter().map(|brain| brain.fitness).collect()
+    }
+
+    //who parents are picked from: the generation just evaluated,
+    //unless the method also keeps the best of the one before
+    fn survive(&self, _parents: Vec<Brain>, children: Vec<Brain>) -> Vec<Brain> {
+        children
+    }
+
+    //whether survive wants the parents, so breed keeps them
+    fn keeps_parents(&self) -> bool {
+        false
+    }
+}
+
+//NaN last, otherwise by value
+pub fn compare(a: f32, b: f32) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => a.partial_cmp(&b).unwrap(),
+    }
+}
+
+//indexes from worst to best
+pub(crate) fn ranked(fitness: &[f32]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..fitness.len()).collect();
+    order.sort_by(|&a, &b| compare(fitness[a], fitness[b]));
+    order
+}
+
+//non negative weights for the proportional methods, None if all 0
+fn weights(fitness: &[f32]) -> Option<Vec<f32>> {
+    let low = fitness
+        .iter()
+        .copied()
+        .filter(|f| f.is_finite())
+        .fold(0.0, f32::min);
+    let weights: Vec<f32> = fitness
+        .iter()
+        .map(|&f| {
+            if f.is_finite() {
+                f - low
+            } else if f > 0.0 {
+                f32::MAX
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let sum: f32 = weights.iter().sum();
+    if sum > 0.0 && sum.is_finite() {
+        Some(weights)
+    } else {
+        None
+    }
+}
+
+//fitness proportional, as in the Floreano paper
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Roulette;
+
+impl Selection for Roulette {
+    fn select<R: Rng + ?Sized>(&self, fitness: &[f32], rng: &mut R) -> usize {
+        let weights = match weights(fitness) {
+            Some(weights) => weights,
+            None => return rng.gen_range(0..fitness.len()),
+        };
+        let goal = rng.gen_range(0.0..weights.iter().sum::<f32>());
+        let mut this_fit = 0.0;
+        for (ix, &fit) in weights.iter().enumerate() {
+            this_fit += fit;
+            if this_fit > goal {
+                return ix;
+            }
+        }
+        fitness.len() - 1
+    }
+}
+
+//best of k brains picked at random. bigger k, more pressure
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tournament {
+    pub k: usize,
+}
+
+impl Default for Tournament {
+    fn default() -> Self {
+        Tournament { k: 3 }
+    }
+}
+
+impl Selection for Tournament {
+    fn select<R: Rng + ?Sized>(&self, fitness: &[f32], rng: &mut R) -> usize {
+        let mut best = rng.gen_range(0..fitness.len());
+        for _ in 1..self.k {
+            let ix = rng.gen_range(0..fitness.len());
+            if compare(fitness[ix], fitness[best]) == Ordering::Greater {
+                best = ix;
+            }
+        }
+        best
+    }
+}
+
+//linear ranking: only the order counts. the best brain is
+//pressure times as likely as average, the worst 2 - pressure
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LinearRank {
+    pub pressure: f32, //1.0 ..= 2.0
+}
+
+impl Default for LinearRank {
+    fn default() -> Self {
+        LinearRank { pressure: 1.5 }
+    }
+}
+
+impl Selection for LinearRank {
+    fn select<R: Rng + ?Sized>(&self, fitness: &[f32], rng: &mut R) -> usize {
+        let order = ranked(fitness);
+        let n = order.len() as f32;
+        if order.len() == 1 {
+            return order[0];
+        }
+        let weight = |rank: usize| {
+            (2.0 - self.pressure) / n + 2.0 * rank as f32 * (self.pressure - 1.0) / (n * (n - 1.0))
+        };
+        let goal = rng.gen_range(0.0..1.0);
+        let mut this_fit = 0.0;
+        for (rank, &ix) in order.iter().enumerate() {
+            this_fit += weight(rank);
+            if this_fit > goal {
+                return ix;
+            }
+        }
+        order[order.len() - 1]
+    }
+}
+
+//uniform pick among the best fraction of the population
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Truncation {
+    pub fraction: f32, //0.0 < fraction <= 1.0
+}
+
+impl Default for Truncation {
+    fn default() -> Self {
+        Truncation { fraction: 0.5 }
+    }
+}
+
+impl Selection for Truncation {
+    fn select<R: Rng + ?Sized>(&self, fitness: &[f32], rng: &mut R) -> usize {
+        let order = ranked(fitness);
+        let keep = ((self.fraction * order.len() as f32).ceil() as usize)
+            .max(1)
+            .min(order.len());
+        order[order.len() - 1 - rng.gen_range(0..keep)]
+    }
+}
+
+//stochastic universal sampling: one spin, n evenly spaced
+//pointers, so a brain gets close to its expected number of picks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StochasticUniversal;
+
+impl Selection for StochasticUniversal {
+    fn select<R: Rng + ?Sized>(&self, fitness: &[f32], rng: &mut R) -> usize {
+        Roulette.select(fitness, rng)
+    }
+
+    fn select_many<R: Rng + ?Sized>(&self, fitness: &[f32], n: usize, rng: &mut R) -> Vec<usize> {
+        //no pointers to space out, e.g. when elitism fills a generation
+        if n == 0 {
+            return Vec::new();
+        }
+        let weights = match weights(fitness) {
+            Some(weights) => weights,
+            None => return (0..n).map(|_| rng.gen_range(0..fitness.len())).collect(),
+        };
+        let step = weights.iter().sum::<f32>() / n as f32;
+        let mut pointer = rng.gen_range(0.0..step);
+        let mut picks = Vec::with_capacity(n);
+        let mut this_fit = 0.0;
+        for (ix, &fit) in weights.iter().enumerate() {
+            this_fit += fit;
+            while pointer < this_fit && picks.len() < n {
+                picks.push(ix);
+                pointer += step;
+            }
+        }
+        //rounding can leave the last pointer just past the end
+        while picks.len() < n {
+            picks.push(ranked(fitness)[fitness.len() - 1]);
+        }
+        //parents are paired up in order, don't mate neighbours
+        picks.shuffle(rng);
+        picks
+    }
+}
+
+//a method picked at run time
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SelectionMethod {
+    Roulette(Roulette),
+    Tournament(Tournament),
+    LinearRank(LinearRank),
+    Truncation(Truncation),
+    StochasticUniversal(StochasticUniversal),
+    Nsga2(Nsga2),
+}
+
+impl Default for SelectionMethod {
+    fn default() -> Self {
+        SelectionMethod::Roulette(Roulette)
+    }
+}
+
+impl Selection for SelectionMethod {
+    fn select<R: Rng + ?Sized>(&self, fitness: &[f32], rng: &mut R) -> usize {
+        match self {
+            SelectionMethod::Roulette(method) => method.select(fitness, rng),
+            SelectionMethod::Tournament(method) => method.select(fitness, rng),
+            SelectionMethod::LinearRank(method) => method.select(fitness, rng),
+            SelectionMethod::Truncation(method) => method.select(fitness, rng),
+            SelectionMethod::StochasticUniversal(method) => method.select(fitness, rng),
+            SelectionMethod::Nsga2(method) => method.select(fitness, rng),
+        }
+    }
+
+    fn select_many<R: Rng + ?Sized>(&self, fitness: &[f32], n: usize, rng: &mut R) -> Vec<usize> {
+        match self {
+            SelectionMethod::Roulette(method) => method.select_many(fitness, n, rng),
+            SelectionMethod::Tournament(method) => method.select_many(fitness, n, rng),
+            SelectionMethod::LinearRank(method) => method.select_many(fitness, n, rng),
+            SelectionMethod::Truncation(method) => method.select_many(fitness, n, rng),
+            SelectionMethod::StochasticUniversal(method) => method.select_many(fitness, n, rng),
+            SelectionMethod::Nsga2(method) => method.select_many(fitness, n, rng),
+        }
+    }
+
+    fn scores(&self, brains: &[Brain]) -> Vec<f32> {
+        match self {
+            SelectionMethod::Nsga2(method) => method.scores(brains),
+            _ => brains.iter().map(|brain| brain.fitness).collect(),
+        }
+    }
+
+    fn survive(&self, parents: Vec<Brain>, children: Vec<Brain>) -> Vec<Brain> {
+        match self {
+            SelectionMethod::Nsga2(method) => method.survive(parents, children),
+            _ => children,
+        }
+    }
+
+    fn keeps_parents(&self) -> bool {
+        match self {
+            SelectionMethod::Nsga2(method) => method.keeps_parents(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn methods() -> Vec<SelectionMethod> {
+        vec![
+            SelectionMethod::Roulette(Roulette),
+            SelectionMethod::Tournament(Tournament::default()),
+            SelectionMethod::LinearRank(LinearRank::default()),
+            SelectionMethod::Truncation(Truncation::default()),
+            SelectionMethod::StochasticUniversal(StochasticUniversal),
+            SelectionMethod::Nsga2(Nsga2),
+        ]
+    }
+
+    #[test]
+    fn odd_fitness_values_are_safe() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let cases: [&[f32]; 5] = [
+            &[0.0, 0.0, 0.0],
+            &[-5.0, -1.0, -3.0],
+            &[f32::NAN, 2.0, 1.0],
+            &[f32::NAN, f32::NAN],
+            &[7.0],
+        ];
+        for method in methods() {
+            for fitness in cases.iter() {
+                let picks = method.select_many(fitness, 20, &mut rng);
+                assert_eq!(picks.len(), 20);
+                assert!(picks.iter().all(|&ix| ix < fitness.len()));
+                assert!(method.select_many(fitness, 0, &mut rng).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn better_brains_are_picked_more() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let fitness = [1.0, f32::NAN, 10.0, -4.0, 5.0];
+        for method in methods() {
+            let mut knt = [0; 5];
+            for ix in method.select_many(&fitness, 5000, &mut rng) {
+                knt[ix] += 1;
+            }
+            assert!(knt[2] > knt[3], "{:?} {:?}", method, knt);
+            assert!(knt[2] > knt[1], "{:?} {:?}", method, knt);
+        }
+        //only the top half is ever picked
+        let picks = Truncation { fraction: 0.4 }.select_many(&fitness, 100, &mut rng);
+        assert!(picks.iter().all(|&ix| ix == 2 || ix == 4));
+    }
+}
@@ -0,0 +1,213 @@
+//Brains and populations on disk.
+//
+//The format goes by the file extension, as with config files:
+//.json is pretty printed JSON that can be read and diffed, anything
+//else is the compact bincode format.
+//
+//Files are written next to their target and renamed over it, so a
+//crash mid write leaves the old file alone rather than half a file.
+//
+//JSON has no NaN or infinity, serde_json writes them as null and
+//can't read that back into an f32, so brains and populations going
+//to a .json file get their non finite scores set to 0 first.
+
+use crate::Population;
+use brain::Brain;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+fn is_json(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("json")
+}
+
+//anything serde can write, e.g. a Generational GA
+pub fn save<T: Serialize, P: AsRef<Path>>(value: &T, path: P) -> Result<(), String> {
+    let path = path.as_ref();
+    let bytes = if is_json(path) {
+        serde_json::to_vec_pretty(value).map_err(|err| err.to_string())
+    } else {
+        bincode::serialize(value).map_err(|err| err.to_string())
+    }
+    .map_err(|err| format!("can't encode {}: {}", path.display(), err))?;
+    write_atomic(path, &bytes).map_err(|err| format!("can't write {}: {}", path.display(), err))
+}
+
+//write to path.tmp, flush it to the disk, then rename it over path
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+pub fn load<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T, String> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|err| format!("can't read {}: {}", path.display(), err))?;
+    if is_json(path) {
+        serde_json::from_slice(&bytes).map_err(|err| err.to_string())
+    } else {
+        bincode::deserialize(&bytes).map_err(|err| err.to_string())
+    }
+    .map_err(|err| format!("bad file {}: {}", path.display(), err))
+}
+
+//0 for NaN and infinities, which JSON can't hold
+fn finite(value: f32) -> f32 {
+    if value.is_finite() {
+        value
+    } else {
+        0.0
+    }
+}
+
+fn finite_scores(brain: &mut Brain) {
+    brain.fitness = finite(brain.fitness);
+    for score in brain.objectives.iter_mut() {
+        *score = finite(*score);
+    }
+}
+
+pub fn save_brain<P: AsRef<Path>>(brain: &Brain, path: P) -> Result<(), String> {
+    if !is_json(path.as_ref()) {
+        return save(brain, path);
+    }
+    let mut brain = brain.clone();
+    finite_scores(&mut brain);
+    save(&brain, path)
+}
+
+pub fn load_brain<P: AsRef<Path>>(path: P) -> Result<Brain, String> {
+    let brain: Brain = load(path)?;
+    brain.validate()?;
+    Ok(brain)
+}
+
+//the brains with their fitness, the generation count, the stats
+//history and the hall of fame
+pub fn save_population<P: AsRef<Path>>(population: &Population, path: P) -> Result<(), String> {
+    if !is_json(path.as_ref()) {
+        return save(population, path);
+    }
+    let mut population = population.clone();
+    for brain in population
+        .brains
+        .iter_mut()
+        .chain(population.parents.iter_mut())
+    {
+        finite_scores(brain);
+    }
+    for entry in population.hall_of_fame.entries.iter_mut() {
+        entry.fitness = finite(entry.fitness);
+        finite_scores(&mut entry.brain);
+    }
+    for fitness in population.parent_fitness.iter_mut() {
+        *fitness = finite(*fitness);
+    }
+    for stats in population.history.iter_mut() {
+        stats.best = finite(stats.best);
+        stats.mean = finite(stats.mean);
+        stats.worst = finite(stats.worst);
+        stats.std_dev = finite(stats.std_dev);
+    }
+    save(&population, path)
+}
+
+pub fn load_population<P: AsRef<Path>>(path: P) -> Result<Population, String> {
+    let population: Population = load(path)?;
+    if population.is_empty() {
+        return Err("the population has no brains".to_string());
+    }
+    let hall = population.hall_of_fame.entries.iter().map(|e| &e.brain);
+    for brain in population.brains.iter().chain(hall) {
+        brain.validate()?;
+    }
+    Ok(population)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn populations_round_trip_as_json_and_binary() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let brains = (0..4)
+            .map(|i| {
+                let mut brain = Brain::random(8, 8, 3, &mut rng)
+                    .unwrap()
+                    .with_weights(2.0, &mut rng);
+                brain.fitness = i as f32 * 10.5;
+                brain
+            })
+            .collect();
+        let mut population = Population::new(brains);
+        population.generation = 7;
+        for brain in population.brains.iter() {
+            population.hall_of_fame.offer(brain, 6);
+        }
+
+        let dir = std::env::temp_dir();
+        for name in &["pop.json", "pop.bin"] {
+            let path = dir.join(format!("{}_{}", std::process::id(), name));
+            save_population(&population, &path).unwrap();
+            let loaded = load_population(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+            assert_eq!(loaded.generation, 7);
+            assert_eq!(loaded.fitness(), population.fitness());
+            assert!(loaded.brains[3].same_genome(&population.brains[3]));
+            assert_eq!(loaded.hall_of_fame.len(), 4);
+        }
+
+        let path = dir.join(format!("{}_brain.json", std::process::id()));
+        fs::write(&path, "{\"fitness\": 1.0}").unwrap();
+        assert!(load_brain(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn nan_fitness_survives_a_json_round_trip() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        let brains = (0..3)
+            .map(|_| Brain::random(8, 8, 3, &mut rng).unwrap())
+            .collect();
+        let mut population = Population::new(brains);
+        population.brains[0].fitness = f32::NAN;
+        population.brains[1].fitness = f32::INFINITY;
+        population.brains[2].fitness = 4.0;
+        population.brains[2].objectives = vec![f32::NAN, 1.0];
+        for brain in population.brains.iter() {
+            population.hall_of_fame.offer(brain, 0);
+        }
+        population.parent_fitness = vec![f32::NAN; 3];
+        population.history.push(population.stats());
+
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("{}_nan_pop.json", std::process::id()));
+        save_population(&population, &path).unwrap();
+        let loaded = load_population(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.fitness(), vec![0.0, 0.0, 4.0]);
+        assert_eq!(loaded.brains[2].objectives, vec![0.0, 1.0]);
+        assert_eq!(loaded.hall_of_fame.len(), population.hall_of_fame.len());
+        assert!(loaded
+            .hall_of_fame
+            .entries
+            .iter()
+            .all(|e| e.fitness.is_finite()));
+        assert_eq!(loaded.parent_fitness, vec![0.0; 3]);
+        assert!(loaded.history[0].mean.is_finite());
+        //a copy is cleaned, not the population itself
+        assert!(population.brains[0].fitness.is_nan());
+
+        let path = dir.join(format!("{}_nan_brain.json", std::process::id()));
+        save_brain(&population.brains[0], &path).unwrap();
+        assert_eq!(load_brain(&path).unwrap().fitness, 0.0);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -27,126 +27,142 @@
 
 use nannou::prelude::*;
 extern crate constants;
-extern crate brain;
 extern crate mover;
-use mover::*;
-
-const MAX_LOOP_KNT: usize = 2000; //can't let them live forever
+extern crate sim;
+use constants::Obstacle;
+use log::{error, info};
+use mover::{Arena, Mover};
+use sim::{Args, Simulation};
 
 fn main() {
+    sim::init_logging();
     //basic spell invocation for nannou
-    nannou::app(model).update(update).run();
+    nannou::app(model).update(update).exit(exit).run();
 }
 
 struct Model {
     //this is the data and function that will be alway available
-    mover: Mover,
-    loop_knt: usize,
-    num_epochs: usize,
+    //the viewer only draws it, the simulation does all the work
+    sim: Simulation,
+    save: Option<std::path::PathBuf>, //--save, written when the window closes
 }
 
 fn model(app: &App) -> Model {
-    let rect = Rect::from_w_h(constants::WIDTH, constants::HEIGHT);
+    let args = Args::from_env().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(2);
+    });
+    let sim = Simulation::from_args(&args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(2);
+    });
     app.new_window()
-        .size(rect.w() as u32, rect.h() as u32)
+        .size(sim.arena.walls.w() as u32, sim.arena.walls.h() as u32)
         .view(view)
         .build()
         .unwrap();
 
-    let start_x = constants::WIDTH / 2.0 - constants::SENSOR_LENGTH + 10.0;
-    let start_y = (constants::HEIGHT / 2.0) - constants::SENSOR_LENGTH;
-
-    let mover = Mover::new(start_x, start_y);
-    let loop_knt = 0;
-    let num_epochs = 0;
     Model {
-        mover,
-        loop_knt,
-        num_epochs,
+        sim,
+        save: args.save,
     }
 }
 
 fn update(app: &App, m: &mut Model, _update: Update) {
-    m.mover.check_dead(app.window_rect());
-    if m.mover.isdead == 0 {
-        m.mover.get_sensor_data(app.window_rect());
-        m.mover.think();
-        m.mover.update_mover();
-        m.loop_knt += 1;
+    //a replay closes the window after its last life
+    if m.sim.finished() {
+        app.quit();
+        return;
     }
-
-    if m.mover.isdead == 1 || m.loop_knt > MAX_LOOP_KNT {
-        //do mutations and updates here
-        //
-        println!("END OF LIFE FITNESS WAS: {}", m.mover.brain.fitness);
-        //get fitnesses for the population before choosing
-        //who to breed/mutate.
-        //
-        if m.num_epochs < constants::NUM_BRAINS {
-            //store old results
-            m.mover.brains[m.mover.brain_index] = m.mover.brain.clone();
-            //get new brain
-            m.mover.brain_index = m.num_epochs;
-            m.mover.brain = m.mover.brains[m.mover.brain_index].clone();
-        } else {
-            //don't want to do this sort but it makes things cleaner.
-            //use the technique in the paper next time.
-
-            m.mover
-                .brains
-                .sort_by(|d2, d1| d1.fitness.partial_cmp(&d2.fitness).unwrap());
-
-            let mut sum_fit = 0.0;
-            for ix in 0..constants::NUM_BRAINS {
-                println!("IX: {} FITNESS: {} ", ix, m.mover.brains[ix].fitness as u32);
-                sum_fit += m.mover.brains[ix].fitness;
-            }
-
-            //pick a new brain
-            //Since already sorted,
-            //pick a random number between 0.0 and 1.0
-            //run down the sorted fitnesses until the sum
-            //of the fitnesses is greater than the random number
-            //
-            let mut this_fit = 0.0;
-            let goal = random_range(0.0, 1.0);
-
-            let mut goal_index = 0;
-            for ix in 0..constants::NUM_BRAINS {
-                this_fit += m.mover.brains[ix].fitness / sum_fit;
-                if this_fit > goal {
-                    goal_index = ix;
-                    break;
-                }
-            }
-            //before replacing brain , see if it should be stored in the
-            //population.
-            if m.mover.brain.fitness >= m.mover.brains[constants::NUM_BRAINS - 1].fitness {
-                m.mover.brains[constants::NUM_BRAINS - 1] = m.mover.brain.clone();
-            }
-            m.mover.brain_index = goal_index;
-            m.mover.brain = m.mover.brains[m.mover.brain_index].clone();
-        } //end of if-else
-
-        m.loop_knt = 0;
-        m.mover.mutate();
-
-        m.mover.reset_mover(constants::WIDTH,constants::HEIGHT);
-
-        m.num_epochs += 1;
-        println!("NUM EPOCHS: {} ", m.num_epochs);
-    } //end of if on dead or frames done
+    m.sim.step();
 } //end of update
 
+//keep the population if --save was given
+fn exit(_app: &App, m: Model) {
+    if let Some(path) = &m.save {
+        match m.sim.save_population(path) {
+            Ok(()) => info!("SAVED: {}", path.display()),
+            Err(err) => error!("{}", err),
+        }
+    }
+}
+
 fn view(app: &App, m: &Model, frame: Frame) {
     // Begin drawing
     let draw = app.draw();
     draw.background().color(WHITE);
 
-    m.mover.display(&draw);
+    display(&m.sim.mover, &draw);
+    display_arena(&m.sim.arena, &draw);
 
     // Write the result of our drawing to the window's frame.
     draw.to_frame(app, &frame).unwrap();
 }
 
+fn display(mover: &Mover, draw: &Draw) {
+    let position = pt2(mover.position.x, mover.position.y);
+    // Display circle at x position
+    if mover.isdead == 0 {
+        draw.rect()
+            .xy(position)
+            .w_h(5.0, 5.0)
+            .rgba(0.8, 0.3, 0.3, 0.5)
+            .stroke(RED)
+            .stroke_weight(2.0);
+    }
+
+    if mover.isdead == 1 {
+        draw.rect()
+            .xy(position)
+            .w_h(5.0, 5.0)
+            .rgba(0.1, 0.3, 0.3, 0.5)
+            .stroke(BLACK)
+            .stroke_weight(2.0);
+    }
 
+    for &[x, y] in &mover.sensor_data {
+        let end_point = pt2(x, y);
+        draw.line()
+            .start(position)
+            .end(end_point)
+            .weight(2.00)
+            .color(BLACK);
+    }
+} //end of display
+
+fn display_arena(arena: &Arena, draw: &Draw) {
+    for obstacle in &arena.obstacles {
+        match obstacle {
+            Obstacle::Box { x, y, w, h } => {
+                draw.rect()
+                    .x_y(*x, *y)
+                    .w_h(*w, *h)
+                    .rgba(0.1, 0.3, 0.3, 0.5)
+                    .stroke(BLACK)
+                    .stroke_weight(2.0);
+            }
+            Obstacle::Circle { x, y, r } => {
+                draw.ellipse()
+                    .x_y(*x, *y)
+                    .radius(*r)
+                    .rgba(0.1, 0.3, 0.3, 0.5)
+                    .stroke(BLACK)
+                    .stroke_weight(2.0);
+            }
+            Obstacle::Polygon { points } => {
+                draw.polygon()
+                    .rgba(0.1, 0.3, 0.3, 0.5)
+                    .stroke(BLACK)
+                    .stroke_weight(2.0)
+                    .points(points.iter().map(|&[x, y]| pt2(x, y)));
+            }
+            Obstacle::Segment { from, to } => {
+                draw.line()
+                    .start(pt2(from[0], from[1]))
+                    .end(pt2(to[0], to[1]))
+                    .weight(constants::SEGMENT_WIDTH)
+                    .color(BLACK);
+            }
+        }
+    }
+} //end of display_arena
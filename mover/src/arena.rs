@@ -0,0 +1,315 @@
+//The arena -- walls all round and obstacles inside.
+//
+//Built from the config's obstacles (or a map file, see
+//SimConfig::load_map). Death checks, the sensors and the viewer
+//all ask the arena, so a new layout needs no new code.
+//
+//The sensors are rays cast from the rover: each shape is
+//intersected exactly, so a reading is the true distance to the
+//nearest thing and costs the same however long the antennae are.
+
+use crate::geom::{pt2, Point2, Rect};
+use crate::Death;
+use constants::{Obstacle, SimConfig, SEGMENT_WIDTH};
+use serde::{Deserialize, Serialize};
+
+//where a ray first touches something
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    pub distance: f32,
+    pub obstacle: Option<usize>, //index in obstacles, None for the walls
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Arena {
+    pub walls: Rect,
+    pub obstacles: Vec<Obstacle>,
+}
+
+impl Arena {
+    pub fn new(config: &SimConfig) -> Self {
+        Arena {
+            walls: Rect::from_w_h(config.width, config.height),
+            obstacles: config.obstacles.clone(),
+        }
+    }
+
+    //what p runs into, if anything. rocks is any obstacle
+    pub fn hit(&self, p: Point2) -> Option<Death> {
+        if !self.walls.contains(p) {
+            Some(Death::Wall)
+        } else if self.obstacles.iter().any(|o| o.contains(p.x, p.y)) {
+            Some(Death::Rocks)
+        } else {
+            None
+        }
+    }
+
+    //the nearest thing along the ray from origin, no further than
+    //max. direction needn't be unit length, distance is along it.
+    //from inside an obstacle the distance is 0
+    pub fn cast(&self, origin: Point2, direction: Point2, max: f32) -> Option<Hit> {
+        let length = direction.x.hypot(direction.y);
+        let d = pt2(direction.x / length, direction.y / length);
+        let walls = Hit {
+            distance: ray_walls(origin, d, &self.walls),
+            obstacle: None,
+        };
+        let nearest = self
+            .obstacles
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, obstacle)| {
+                ray(obstacle, origin, d).map(|distance| Hit {
+                    distance,
+                    obstacle: Some(ix),
+                })
+            })
+            .fold(walls, |best, hit| {
+                if hit.distance < best.distance {
+                    hit
+                } else {
+                    best
+                }
+            });
+        if nearest.distance <= max {
+            Some(nearest)
+        } else {
+            None
+        }
+    }
+}
+
+//rays below are origin o + t * d with d unit length. each gives the
+//smallest t >= 0 on the shape, if there is one
+
+fn cross(a: Point2, b: Point2) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+fn dot(a: Point2, b: Point2) -> f32 {
+    a.x * b.x + a.y * b.y
+}
+
+fn ray(obstacle: &Obstacle, o: Point2, d: Point2) -> Option<f32> {
+    match obstacle {
+        Obstacle::Box { x, y, w, h } => {
+            let (left, right) = (x - w / 2.0, x + w / 2.0);
+            let (bottom, top) = (y - h / 2.0, y + h / 2.0);
+            ray_box(o, d, [left, right, bottom, top])
+        }
+        Obstacle::Circle { x, y, r } => ray_circle(o, d, pt2(*x, *y), *r),
+        Obstacle::Polygon { points } => {
+            if obstacle.contains(o.x, o.y) {
+                return Some(0.0);
+            }
+            let n = points.len();
+            (0..n)
+                .filter_map(|i| {
+                    let [ax, ay] = points[i];
+                    let [bx, by] = points[(i + 1) % n];
+                    ray_segment(o, d, pt2(ax, ay), pt2(bx, by))
+                })
+                .fold(None, |best: Option<f32>, t| {
+                    Some(best.map_or(t, |b| b.min(t)))
+                })
+        }
+        //SEGMENT_WIDTH thick as for dying on it: a capsule, the
+        //line moved out to either side and a half circle on each end
+        Obstacle::Segment { from, to } => {
+            if obstacle.contains(o.x, o.y) {
+                return Some(0.0);
+            }
+            let (a, b) = (pt2(from[0], from[1]), pt2(to[0], to[1]));
+            let r = SEGMENT_WIDTH / 2.0;
+            let length = (b.x - a.x).hypot(b.y - a.y);
+            let n = pt2(-(b.y - a.y) / length * r, (b.x - a.x) / length * r);
+            let side = |k: f32| {
+                let shift = |p: Point2| pt2(p.x + k * n.x, p.y + k * n.y);
+                ray_segment(o, d, shift(a), shift(b))
+            };
+            vec![
+                side(1.0),
+                side(-1.0),
+                ray_circle(o, d, a, r),
+                ray_circle(o, d, b, r),
+            ]
+            .into_iter()
+            .flatten()
+            .fold(None, |best: Option<f32>, t| {
+                Some(best.map_or(t, |b| b.min(t)))
+            })
+        }
+    }
+}
+
+fn ray_circle(o: Point2, d: Point2, center: Point2, r: f32) -> Option<f32> {
+    let f = pt2(o.x - center.x, o.y - center.y);
+    let b = dot(f, d);
+    let c = dot(f, f) - r * r;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    let disc = b * b - c;
+    let t = -b - disc.sqrt();
+    if disc < 0.0 || t < 0.0 {
+        None
+    } else {
+        Some(t)
+    }
+}
+
+//slabs: the ray is inside the box between near and far
+fn ray_box(o: Point2, d: Point2, [left, right, bottom, top]: [f32; 4]) -> Option<f32> {
+    let (mut near, mut far) = (0.0f32, f32::INFINITY);
+    for &(o, d, low, high) in &[(o.x, d.x, left, right), (o.y, d.y, bottom, top)] {
+        if d == 0.0 {
+            if o < low || o > high {
+                return None;
+            }
+            continue;
+        }
+        let (t0, t1) = ((low - o) / d, (high - o) / d);
+        near = near.max(t0.min(t1));
+        far = far.min(t0.max(t1));
+        if near > far {
+            return None;
+        }
+    }
+    Some(near)
+}
+
+fn ray_segment(o: Point2, d: Point2, a: Point2, b: Point2) -> Option<f32> {
+    let e = pt2(b.x - a.x, b.y - a.y);
+    let w = pt2(a.x - o.x, a.y - o.y);
+    let denom = cross(d, e);
+    if denom == 0.0 {
+        //parallel, a hit only if it is on the same line
+        if cross(w, d) != 0.0 {
+            return None;
+        }
+        let (ta, tb) = (dot(w, d), dot(pt2(b.x - o.x, b.y - o.y), d));
+        return if ta.max(tb) < 0.0 {
+            None
+        } else {
+            Some(ta.min(tb).max(0.0))
+        };
+    }
+    let t = cross(w, e) / denom;
+    let s = cross(w, d) / denom;
+    if t >= 0.0 && (0.0..=1.0).contains(&s) {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+//the way out of the arena, always there
+fn ray_walls(o: Point2, d: Point2, walls: &Rect) -> f32 {
+    let exit = |o: f32, d: f32, low: f32, high: f32| {
+        if d > 0.0 {
+            (high - o) / d
+        } else if d < 0.0 {
+            (low - o) / d
+        } else {
+            f32::INFINITY
+        }
+    };
+    let tx = exit(o.x, d.x, walls.left(), walls.right());
+    let ty = exit(o.y, d.y, walls.bottom(), walls.top());
+    tx.min(ty).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_shape_has_an_inside() {
+        let mut config = SimConfig::default();
+        config.obstacles.extend(vec![
+            Obstacle::Circle {
+                x: 100.0,
+                y: 100.0,
+                r: 20.0,
+            },
+            Obstacle::Polygon {
+                points: vec![[-100.0, 100.0], [-150.0, 100.0], [-100.0, 150.0]],
+            },
+            Obstacle::Segment {
+                from: [100.0, -150.0],
+                to: [150.0, -100.0],
+            },
+        ]);
+        let arena = Arena::new(&config);
+        let hit = |x, y| arena.hit(pt2(x, y));
+        assert_eq!(hit(50.0, -50.0), Some(Death::Rocks));
+        assert_eq!(hit(51.0, 0.0), None);
+        assert_eq!(hit(110.0, 115.0), Some(Death::Rocks));
+        assert_eq!(hit(115.0, 115.0), None);
+        assert_eq!(hit(-110.0, 110.0), Some(Death::Rocks));
+        assert_eq!(hit(-140.0, 140.0), None);
+        assert_eq!(hit(125.5, -125.0), Some(Death::Rocks));
+        assert_eq!(hit(128.0, -125.0), None);
+        assert_eq!(hit(201.0, 0.0), Some(Death::Wall));
+        assert_eq!(hit(-150.0, -150.0), None);
+    }
+
+    #[test]
+    fn rays_stop_at_the_nearest_thing() {
+        let mut config = SimConfig::default();
+        config.obstacles.extend(vec![
+            Obstacle::Circle {
+                x: 100.0,
+                y: 0.0,
+                r: 10.0,
+            },
+            Obstacle::Polygon {
+                points: vec![[-100.0, 100.0], [-150.0, 100.0], [-100.0, 150.0]],
+            },
+            Obstacle::Segment {
+                from: [0.0, -150.0],
+                to: [0.0, -100.0],
+            },
+        ]);
+        let arena = Arena::new(&config);
+        let cast = |x, y, dx, dy| arena.cast(pt2(x, y), pt2(dx, dy), 1000.0).unwrap();
+        let near = |a: f32, b: f32| (a - b).abs() < 1e-4;
+
+        //box from the right, circle from the left
+        let hit = cast(80.0, 0.0, -1.0, 0.0);
+        assert_eq!(hit.obstacle, Some(0));
+        assert!(near(hit.distance, 30.0));
+        let hit = cast(80.0, 0.0, 1.0, 0.0);
+        assert_eq!(hit.obstacle, Some(1));
+        assert!(near(hit.distance, 10.0));
+        //diagonals are measured along the ray, not per step
+        let hit = cast(-110.0, 90.0, 0.0, 1.0);
+        assert_eq!(hit.obstacle, Some(2));
+        assert!(near(hit.distance, 10.0));
+        //segments are SEGMENT_WIDTH thick, as for dying on them
+        let hit = cast(-90.0, -125.0, 1.0, 0.0);
+        assert_eq!(hit.obstacle, Some(3));
+        assert!(near(hit.distance, 89.0));
+        let hit = cast(150.0, 150.0, 1.0, 1.0);
+        assert_eq!(hit.obstacle, None);
+        assert!(near(hit.distance, 50.0 * 2.0f32.sqrt()));
+        //along the segment's own line onto its rounded end
+        let hit = cast(0.0, -190.0, 0.0, 1.0);
+        assert_eq!(hit.obstacle, Some(3));
+        assert!(near(hit.distance, 39.0));
+        //the sensors see a wall where the rover would die on it
+        //from the side and onto the top end, aiming at the line
+        for &(x, y, aim) in &[(-30.0f32, -160.0f32, -125.0f32), (30.0, -70.0, -100.0)] {
+            let (dx, dy) = (-x, aim - y);
+            let hit = cast(x, y, dx, dy);
+            assert_eq!(hit.obstacle, Some(3));
+            let length = dx.hypot(dy);
+            let at = |t: f32| pt2(x + dx / length * t, y + dy / length * t);
+            assert_eq!(arena.hit(at(hit.distance - 1e-3)), None);
+            assert_eq!(arena.hit(at(hit.distance + 1e-3)), Some(Death::Rocks));
+        }
+        //too short to reach
+        assert_eq!(arena.cast(pt2(80.0, 0.0), pt2(1.0, 0.0), 5.0), None);
+    }
+}
@@ -0,0 +1,289 @@
+//Fitness -- what a life is scored on.
+//
+//The original score was one point per step without a turn, which
+//rates a rover circling in a quiet corner as high as one crossing
+//the arena. The components here each score one thing and a
+//Weighted sum of them is what ends up in brain.fitness:
+//
+//  straight   steps without a turn, the original score
+//  floreano   V * (1 - sqrt(dv)) * (1 - i) per step, from the paper:
+//             speed, going straight and keeping away from things
+//  survival   steps alive
+//  coverage   grid cells of the arena visited
+//  clearance  1 - i per step, i the strongest sensor reading
+
+use crate::geom::Point2;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+//what the rover did in one step, all but position in 0..=1
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StepInfo {
+    pub position: Point2,
+    pub speed: f32,     //of the top speed
+    pub turn: f32,      //heading change, a half turn is 1
+    pub proximity: f32, //strongest sensor, 1 is touching
+}
+
+pub trait FitnessFunction {
+    //one step of the rover's life
+    fn step(&mut self, step: &StepInfo);
+    //score of the life so far
+    fn score(&self) -> f32;
+    //forget the last life
+    fn reset(&mut self);
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Straight {
+    pub steps: usize,
+}
+
+impl FitnessFunction for Straight {
+    fn step(&mut self, step: &StepInfo) {
+        if step.turn == 0.0 {
+            self.steps += 1;
+        }
+    }
+
+    fn score(&self) -> f32 {
+        self.steps as f32
+    }
+
+    fn reset(&mut self) {
+        self.steps = 0;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Floreano {
+    pub sum: f32,
+}
+
+impl FitnessFunction for Floreano {
+    fn step(&mut self, step: &StepInfo) {
+        self.sum += step.speed * (1.0 - step.turn.sqrt()) * (1.0 - step.proximity);
+    }
+
+    fn score(&self) -> f32 {
+        self.sum
+    }
+
+    fn reset(&mut self) {
+        self.sum = 0.0;
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Survival {
+    pub steps: usize,
+}
+
+impl FitnessFunction for Survival {
+    fn step(&mut self, _step: &StepInfo) {
+        self.steps += 1;
+    }
+
+    fn score(&self) -> f32 {
+        self.steps as f32
+    }
+
+    fn reset(&mut self) {
+        self.steps = 0;
+    }
+}
+
+//the arena cut into cell by cell squares
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Coverage {
+    pub cell: f32,
+    pub visited: BTreeSet<(i32, i32)>,
+}
+
+impl Coverage {
+    pub fn new(cell: f32) -> Self {
+        Coverage {
+            cell,
+            visited: BTreeSet::new(),
+        }
+    }
+}
+
+impl FitnessFunction for Coverage {
+    fn step(&mut self, step: &StepInfo) {
+        let x = (step.position.x / self.cell).floor() as i32;
+        let y = (step.position.y / self.cell).floor() as i32;
+        self.visited.insert((x, y));
+    }
+
+    fn score(&self) -> f32 {
+        self.visited.len() as f32
+    }
+
+    fn reset(&mut self) {
+        self.visited.clear();
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Clearance {
+    pub sum: f32,
+}
+
+impl FitnessFunction for Clearance {
+    fn step(&mut self, step: &StepInfo) {
+        self.sum += 1.0 - step.proximity;
+    }
+
+    fn score(&self) -> f32 {
+        self.sum
+    }
+
+    fn reset(&mut self) {
+        self.sum = 0.0;
+    }
+}
+
+//one of the above, so a Weighted sum can be built from the config
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FitnessComponent {
+    Straight(Straight),
+    Floreano(Floreano),
+    Survival(Survival),
+    Coverage(Coverage),
+    Clearance(Clearance),
+}
+
+impl FitnessComponent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FitnessComponent::Straight(_) => "straight",
+            FitnessComponent::Floreano(_) => "floreano",
+            FitnessComponent::Survival(_) => "survival",
+            FitnessComponent::Coverage(_) => "coverage",
+            FitnessComponent::Clearance(_) => "clearance",
+        }
+    }
+}
+
+impl FitnessFunction for FitnessComponent {
+    fn step(&mut self, step: &StepInfo) {
+        match self {
+            FitnessComponent::Straight(f) => f.step(step),
+            FitnessComponent::Floreano(f) => f.step(step),
+            FitnessComponent::Survival(f) => f.step(step),
+            FitnessComponent::Coverage(f) => f.step(step),
+            FitnessComponent::Clearance(f) => f.step(step),
+        }
+    }
+
+    fn score(&self) -> f32 {
+        match self {
+            FitnessComponent::Straight(f) => f.score(),
+            FitnessComponent::Floreano(f) => f.score(),
+            FitnessComponent::Survival(f) => f.score(),
+            FitnessComponent::Coverage(f) => f.score(),
+            FitnessComponent::Clearance(f) => f.score(),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            FitnessComponent::Straight(f) => f.reset(),
+            FitnessComponent::Floreano(f) => f.reset(),
+            FitnessComponent::Survival(f) => f.reset(),
+            FitnessComponent::Coverage(f) => f.reset(),
+            FitnessComponent::Clearance(f) => f.reset(),
+        }
+    }
+}
+
+//sum of weight * score over the terms
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Weighted {
+    pub terms: Vec<(f32, FitnessComponent)>,
+}
+
+impl Weighted {
+    //of the terms, in order. these are the objectives of a brain
+    pub fn names(&self) -> Vec<&'static str> {
+        self.terms.iter().map(|(_, term)| term.name()).collect()
+    }
+}
+
+impl FitnessFunction for Weighted {
+    fn step(&mut self, step: &StepInfo) {
+        for (_, term) in self.terms.iter_mut() {
+            term.step(step);
+        }
+    }
+
+    fn score(&self) -> f32 {
+        self.terms.iter().map(|(w, term)| w * term.score()).sum()
+    }
+
+    fn reset(&mut self) {
+        for (_, term) in self.terms.iter_mut() {
+            term.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::pt2;
+
+    fn step(x: f32, turn: f32, proximity: f32) -> StepInfo {
+        StepInfo {
+            position: pt2(x, 0.0),
+            speed: 1.0,
+            turn,
+            proximity,
+        }
+    }
+
+    #[test]
+    fn components_score_what_they_say() {
+        let steps = [
+            step(0.0, 0.0, 0.0),
+            step(1.0, 0.25, 0.5),
+            step(25.0, 0.0, 1.0),
+        ];
+        let mut straight = Straight::default();
+        let mut floreano = Floreano::default();
+        let mut survival = Survival::default();
+        let mut coverage = Coverage::new(20.0);
+        let mut clearance = Clearance::default();
+        for s in &steps {
+            straight.step(s);
+            floreano.step(s);
+            survival.step(s);
+            coverage.step(s);
+            clearance.step(s);
+        }
+        assert_eq!(straight.score(), 2.0);
+        //1 for the first step, .5 * .5 for the second, 0 at the wall
+        assert_eq!(floreano.score(), 1.25);
+        assert_eq!(survival.score(), 3.0);
+        assert_eq!(coverage.score(), 2.0);
+        assert_eq!(clearance.score(), 1.5);
+        coverage.reset();
+        assert_eq!(coverage.score(), 0.0);
+    }
+
+    #[test]
+    fn weighted_sums_its_terms() {
+        let mut fitness = Weighted {
+            terms: vec![
+                (1.0, FitnessComponent::Straight(Straight::default())),
+                (0.5, FitnessComponent::Survival(Survival::default())),
+            ],
+        };
+        fitness.step(&step(0.0, 0.0, 0.0));
+        fitness.step(&step(0.0, 0.25, 0.0));
+        assert_eq!(fitness.score(), 1.0 + 0.5 * 2.0);
+        fitness.reset();
+        assert_eq!(fitness.score(), 0.0);
+    }
+}
@@ -0,0 +1,64 @@
+//Just enough 2D geometry for the rover and its arena.
+//Kept here so the simulation does not need nannou (or a GPU)
+//to run. The viewer converts to nannou types when drawing.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Point2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+pub fn pt2(x: f32, y: f32) -> Point2 {
+    Point2 { x, y }
+}
+
+//axis aligned rectangle, same accessors as nannou's Rect
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+}
+
+impl Rect {
+    //centered on the origin like a nannou window rect
+    pub fn from_w_h(w: f32, h: f32) -> Self {
+        Rect {
+            left: -w / 2.0,
+            right: w / 2.0,
+            bottom: -h / 2.0,
+            top: h / 2.0,
+        }
+    }
+
+    pub fn left(&self) -> f32 {
+        self.left
+    }
+
+    pub fn right(&self) -> f32 {
+        self.right
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.bottom
+    }
+
+    pub fn top(&self) -> f32 {
+        self.top
+    }
+
+    pub fn w(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn h(&self) -> f32 {
+        self.top - self.bottom
+    }
+
+    pub fn contains(&self, p: Point2) -> bool {
+        (self.left..=self.right).contains(&p.x) && (self.bottom..=self.top).contains(&p.y)
+    }
+}
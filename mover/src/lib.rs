@@ -1,44 +1,81 @@
 extern crate brain;
 extern crate constants;
+extern crate evolution;
 use brain::*;
-use nannou::prelude::*;
-
+use constants::{MutationKind, NeuronKind, SimConfig, WiringKind};
+use evolution::{FlipMutation, Mutation, MutationMethod, Population, RateMutation};
+use log::{debug, trace};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+mod arena;
+mod fitness;
+mod geom;
+pub use arena::{Arena, Hit};
+pub use fitness::{
+    Clearance, Coverage, FitnessComponent, FitnessFunction, Floreano, StepInfo, Straight, Survival,
+    Weighted,
+};
+pub use geom::{pt2, Point2, Rect};
+
+//how a life ended. rocks is any obstacle in the arena
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Death {
+    Wall,
+    Rocks,
+    Timeout,
+}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Mover {
-   pub position: Point2,
-   pub  angle_index: usize,
-   pub  old_angle_index: usize,
-   pub velocity_x: f32,
-   pub  velocity_y: f32,
-   pub  multiplier: i32,
-   pub sensor_data: [[f32; 2]; constants::NUM_SENSORS],
-   pub sensor_data_vector: [u8; constants::NUM_NEURONS],
-   pub isdead: i32,
-   pub brain_index: usize,
-   pub  brain: Brain,
-   pub  brains: Vec<Brain>,
+    pub position: Point2,
+    pub angle_index: usize,
+    pub old_angle_index: usize,
+    pub velocity_x: f32,
+    pub velocity_y: f32,
+    pub multiplier: i32,
+    pub sensor_data: Vec<[f32; 2]>, //end of each antenna, left to right
+    pub sensor_data_vector: Vec<u8>,
+    pub proximity: f32, //strongest sensor reading, 0..=1
+    pub isdead: i32,
+    pub death: Option<Death>, //set with isdead, timeouts by the simulation
+    pub turns: usize,         //heading changes this life
+    pub brain_index: usize,
+    pub brain: Brain,
+    pub population: Population,
+    pub network: Network,
+    pub fitness: Weighted,        //scores the life into brain.fitness
+    pub mutation: MutationMethod, //kept, so adapt_factor can tune its rates
+    pub children: usize,          //lived since the last adapt
+    pub better_children: usize,   //of them, the ones that beat their parent
+    pub config: SimConfig,
 }
 
 impl Mover {
-    pub fn new(x: f32, y: f32) -> Self {
+    pub fn new<R: Rng + ?Sized>(x: f32, y: f32, config: &SimConfig, rng: &mut R) -> Self {
         let position = pt2(x, y);
-        let angle_index = random_range(0, constants::NUM_ANGLES);
-        let old_angle_index = random_range(0, constants::NUM_ANGLES);
+        let angle_index = rng.gen_range(0..constants::NUM_ANGLES);
+        let old_angle_index = rng.gen_range(0..constants::NUM_ANGLES);
         let velocity_x = constants::ANGLES_DX[angle_index];
         let velocity_y = constants::ANGLES_DY[angle_index];
         let multiplier = 1;
-        let sensor_data = [[0.0; 2]; constants::NUM_SENSORS];
-        let sensor_data_vector = [0u8; constants::NUM_NEURONS];
+        let sensor_data = vec![[0.0; 2]; config.num_sensors];
+        let sensor_data_vector = vec![0u8; config.num_neurons];
+        let proximity = 0.0;
         let isdead = 0;
-        let brain_index = random_range(0, constants::NUM_BRAINS);
+        let death = None;
+        let turns = 0;
+        let brain_index = rng.gen_range(0..config.num_brains);
         let mut brains = Vec::new();
-        for _ in 0..constants::NUM_BRAINS {
-            brains.push(brain::Brain::new());
+        for _ in 0..config.num_brains {
+            brains.push(random_brain(config, rng));
         }
-        let brain = brain::Brain::new();
+        let brain = random_brain(config, rng);
+        let network = network(config, &brain);
 
-        //Floreano -- 8 bit brain
+        //Floreano -- 8 bit brain by default, num_neurons in the config
 
         Mover {
             position,
@@ -49,108 +86,75 @@ impl Mover {
             multiplier,
             sensor_data,
             sensor_data_vector,
+            proximity,
             isdead,
+            death,
+            turns,
             brain_index,
             brain,
-            brains,
+            population: Population::new(brains).with_hall_of_fame(config.hall_of_fame),
+            network,
+            fitness: fitness(config),
+            mutation: mutation(config),
+            children: 0,
+            better_children: 0,
+            config: config.clone(),
         }
     } //end of Mover new
 
-    pub fn think(&mut self) {
+    pub fn think<R: Rng + ?Sized>(&mut self, rng: &mut R) {
         //see paper cited above
         self.build_sensor_data_vector();
 
         //In most cases , the sensor_data_vector will be all zeros.
         //so add some bias to make something happen.
-        //
-        let mut knt = 0;
-        for ik in 0..constants::NUM_SENSORS as usize {
-            knt += self.sensor_data_vector[ik];
-        }
-        if knt <= 0 {
-            let ridx = random_range(0, 8);
+        //every sensor's lines count, not just the first few
+        if self.sensor_data_vector.iter().all(|&line| line == 0) {
+            let ridx = rng.gen_range(0..self.brain.num_inputs);
             self.sensor_data_vector[ridx] = 1;
         }
 
-        let leaking_constant = 1;
-        let mut temp_outps = [0u8; constants::NUM_NEURONS];
-        let inps = self.sensor_data_vector.clone();
-        let mut memb = [0u8; constants::NUM_NEURONS];
-        let mut outps = [0u8; constants::NUM_NEURONS];
-        let mut fire_knt = [0; constants::NUM_SENSORS];
-        let settling_time = 20; //loop through settling_time times
-
-        for _epoch in 0..settling_time {
-            for nindex in 0..constants::NUM_NEURONS {
-                memb[nindex] = 0;
-                if outps[nindex] == 0 {
-                    //not in refactory state
-                    for ilink in 0..constants::NUM_NEURONS {
-                        memb[nindex] += inps[nindex] * self.brain.iconn[nindex][ilink];
-                    } //end of loop on ilink
-                      //count from other neurons with positive sign
-                    for ilink in 0..constants::NUM_NEURONS as usize {
-                        let stuff = outps[nindex] * self.brain.nconn[nindex][ilink];
-                        if self.brain.xsign[ilink] > 0 {
-                            //positives
-                            memb[nindex] += stuff;
-                        }
-                        if self.brain.xsign[ilink] <= 0 {
-                            //negatives
-                            if stuff <= memb[nindex] {
-                                memb[nindex] -= stuff;
-                            } else {
-                                memb[nindex] = 0;
-                            } //end of if on sign less than 0
-                        } //end of if on <=0
-                    } //end of loop on ilink
-                } //end of not refactory
-
-                //fire or not !
-                let r: i32 = random_range(-2, 3);
-                let thres: i32 = 3;
-                if memb[nindex] as i32 >= (thres + r) {
-                    temp_outps[nindex] = 1;
-                    memb[nindex] = 0;
-                } else {
-                    temp_outps[nindex] = 0;
-                }
-                //leakage
-                if memb[nindex] >= leaking_constant {
-                    memb[nindex] -= leaking_constant;
-                }
-            } //end of pass through all neurons
-
-            outps = temp_outps.clone();
-            fire_knt[0] += temp_outps[0] + temp_outps[1];
-            fire_knt[1] += temp_outps[3] + temp_outps[4];
-            fire_knt[2] += temp_outps[6] + temp_outps[7];
-
-            temp_outps = [0; constants::NUM_NEURONS];
-        } //end of settling_time loop
+        //stateful networks pick up where the last tick left off
+        if !self.config.stateful {
+            self.network.reset(&self.brain);
+        }
+        let settling_time = self.config.settling_time; //loop through settling_time times
+        let train = self
+            .network
+            .run(&self.brain, &self.sensor_data_vector, settling_time, rng);
+
+        //spikes per output group: turn left, go straight, turn right
+        let fire_knt: Vec<u32> = (0..self.brain.num_outputs)
+            .map(|iout| train.count_group(self.brain.output_group(iout)))
+            .collect();
+        trace!(
+            target: "brain",
+            "INPUTS: {:?} SPIKES: {:?}",
+            self.sensor_data_vector,
+            fire_knt
+        );
 
         let mut min_index = 1; //go straight if nothing happens;
-        let mut min_value = 99;
+        let mut min_value = u32::MAX;
 
         //choose a direction based on sensor.
-        for i in 0..constants::NUM_SENSORS {
-            if fire_knt[i] <= min_value {
-                min_value = fire_knt[i];
+        for (i, &knt) in fire_knt.iter().enumerate() {
+            if knt <= min_value {
+                min_value = knt;
                 min_index = i;
             }
-            fire_knt[i] = 0;
         }
 
         let mut new_angle_index = self.angle_index;
         if min_index == 0 {
-            new_angle_index = new_angle_index + 1;
+            new_angle_index += 1;
             if new_angle_index > constants::NUM_ANGLES - 1 {
                 new_angle_index = 0;
             }
         }
         if min_index == 2 {
             if new_angle_index > 0 {
-                new_angle_index = new_angle_index - 1;
+                new_angle_index -= 1;
             } else {
                 new_angle_index = constants::NUM_ANGLES - 1;
             }
@@ -169,284 +173,447 @@ impl Mover {
         self.position.x += self.velocity_x;
         self.position.y += self.velocity_y;
 
-        //Get rewarded for going more or less straight
-        if self.angle_index == self.old_angle_index {
-            self.brain.fitness += 1.0;
-        }
-    } //end of update function
-
-    pub fn display(&self, draw: &Draw) {
-        // Display circle at x position
-        if self.isdead == 0 {
-            draw.rect()
-                .xy(self.position)
-                .w_h(5.0, 5.0)
-                .rgba(0.8, 0.3, 0.3, 0.5)
-                .stroke(RED)
-                .stroke_weight(2.0);
+        //heading change in 45 degree steps either way, 4 is a half turn
+        let half = constants::NUM_ANGLES / 2;
+        let diff = (self.angle_index + constants::NUM_ANGLES - self.old_angle_index)
+            % constants::NUM_ANGLES;
+        let turn = diff.min(constants::NUM_ANGLES - diff);
+        if turn > 0 {
+            self.turns += 1;
         }
 
-        if self.isdead == 1 {
-            draw.rect()
-                .xy(self.position)
-                .w_h(5.0, 5.0)
-                .rgba(0.1, 0.3, 0.3, 0.5)
-                .stroke(BLACK)
-                .stroke_weight(2.0);
-        }
-
-        for isensor in 0..constants::NUM_SENSORS {
-            let end_point = pt2(self.sensor_data[isensor][0], self.sensor_data[isensor][1]);
-            draw.line()
-                .start(self.position)
-                //.end(self.sensor_data[isensor][0],self.sensor_data[isensor][1])
-                .end(end_point)
-                .weight(2.00)
-                .color(BLACK);
-        }
+        //the rover has one speed, so V is 1 whenever it moves
+        let step = StepInfo {
+            position: self.position,
+            speed: (self.multiplier.abs() as f32).min(1.0),
+            turn: turn as f32 / half as f32,
+            proximity: self.proximity,
+        };
+        self.fitness.step(&step);
+        self.brain.fitness = self.fitness.score();
+        self.brain.objectives.clear();
+        let scores = self.fitness.terms.iter().map(|(_, term)| term.score());
+        self.brain.objectives.extend(scores);
+    } //end of update function
 
-        draw.rect()
-            .x_y(0.0, 0.0)
-            .w_h(100.0, 100.0)
-            .rgba(0.1, 0.3, 0.3, 0.5)
-            .stroke(BLACK)
-            .stroke_weight(2.0);
-    }
     pub fn build_sensor_data_vector(&mut self) {
-        self.sensor_data_vector = [0u8; constants::NUM_NEURONS];
-        for i in 0..constants::NUM_SENSORS {
+        let num_inputs = self.brain.num_inputs;
+        self.sensor_data_vector = vec![0u8; num_inputs];
+        self.proximity = 0.0;
+        let num_sensors = self.config.num_sensors;
+        for i in 0..num_sensors {
             let dx = self.sensor_data[i][0] - self.position.x;
             let dy = self.sensor_data[i][1] - self.position.y;
             let mut dist = dx.hypot(dy);
-            if dist > constants::SENSOR_LENGTH {
-                dist = constants::SENSOR_LENGTH;
+            if dist > self.config.sensor_length {
+                dist = self.config.sensor_length;
             }
             //from paper scale is based on reflected light strength
             //so more reflection closer to wall
             //
-            let junkf = 1.0 - dist / constants::SENSOR_LENGTH;
-
-            if junkf >= 0.80 {
-                if i == 0 {
-                    self.sensor_data_vector[0] = 1;
-                    self.sensor_data_vector[1] = 1;
-                    self.sensor_data_vector[2] = 1;
-                }
-                if i == 1 {
-                    self.sensor_data_vector[3] = 1;
-                    self.sensor_data_vector[4] = 1;
-                }
-                if i == 2 {
-                    self.sensor_data_vector[5] = 1;
-                    self.sensor_data_vector[6] = 1;
-                    self.sensor_data_vector[7] = 1;
-                }
-            } //end of if on .80
-
-            if junkf >= 0.50 && junkf < 0.80 {
-                if i == 0 {
-                    self.sensor_data_vector[0] = 0;
-                    self.sensor_data_vector[1] = 1;
-                    self.sensor_data_vector[2] = 1;
-                }
-                if i == 1 {
-                    self.sensor_data_vector[3] = 1;
-                    self.sensor_data_vector[4] = 1;
-                }
-                if i == 2 {
-                    self.sensor_data_vector[5] = 0;
-                    self.sensor_data_vector[6] = 1;
-                    self.sensor_data_vector[7] = 1;
-                }
-            } //end of if on .50
-
-            if junkf >= 0.25 && junkf < 0.50 {
-                if i == 0 {
-                    self.sensor_data_vector[0] = 0;
-                    self.sensor_data_vector[1] = 0;
-                    self.sensor_data_vector[2] = 1;
-                }
-                if i == 1 {
-                    self.sensor_data_vector[3] = 0;
-                    self.sensor_data_vector[4] = 1;
-                }
-                if i == 2 {
-                    self.sensor_data_vector[5] = 0;
-                    self.sensor_data_vector[6] = 0;
-                    self.sensor_data_vector[7] = 1;
-                }
-            } //end of if 25 - 50
-              //otherwise take defaults of zeros
+            let junkf = 1.0 - dist / self.config.sensor_length;
+            self.proximity = self.proximity.max(junkf);
+
+            let level = if junkf >= 0.80 {
+                3
+            } else if junkf >= 0.50 {
+                2
+            } else if junkf >= 0.25 {
+                1
+            } else {
+                0 //otherwise take defaults of zeros
+            };
+
+            //thermometer code on this sensor's input lines,
+            //filled from the outer end. For the 8 bit brain
+            //that is 3, 2 and 3 lines for the three sensors.
+            let lines = sensor_lines(i, num_sensors, num_inputs);
+            #[allow(clippy::manual_div_ceil)] //div_ceil needs rust 1.73
+            let on = (level * lines.len() + 2) / 3;
+            for line in lines.end - on..lines.end {
+                self.sensor_data_vector[line] = 1;
+            }
         } //end of loop on sensors
     } //end of build_vector
 
-    pub fn check_dead(&mut self, rect: Rect) {
+    pub fn check_dead(&mut self, arena: &Arena) {
+        let rect = arena.walls;
         if self.position.x > rect.right() {
             self.position.x = rect.right();
             self.isdead = 1;
-            println!("DEAD ON WALL");
+            self.death = Some(Death::Wall);
+            debug!(target: "mover", "DEAD ON WALL");
             return;
         }
 
         if self.position.x < rect.left() {
             self.position.x = rect.left();
             self.isdead = 1;
-            println!("DEAD ON WALL");
+            self.death = Some(Death::Wall);
+            debug!(target: "mover", "DEAD ON WALL");
             return;
         }
 
         if self.position.y < rect.bottom() {
             self.position.y = rect.bottom();
             self.isdead = 1;
-            println!("DEAD ON WALL");
+            self.death = Some(Death::Wall);
+            debug!(target: "mover", "DEAD ON WALL");
             return;
         }
 
         if self.position.y > rect.top() {
             self.position.y = rect.top();
             self.isdead = 1;
-            println!("DEAD ON WALL");
+            self.death = Some(Death::Wall);
+            debug!(target: "mover", "DEAD ON WALL");
             return;
         }
 
-        //check against middle box
-        if self.position.x >= -50.0
-            && self.position.x <= 50.0
-            && self.position.y <= 50.0
-            && self.position.y >= -50.0
-        {
+        if arena.hit(self.position).is_some() {
             self.isdead = 1;
-            println!("DEAD ON ROCKS");
-            return;
-        }
-    } //end of check_dead
-
-    pub fn check_collisions(&mut self, xpos: f32, ypos: f32, rect: Rect) -> u32 {
-        //this is only for sensors... no dying
-
-        if xpos > rect.right() {
-            return 1;
+            self.death = Some(Death::Rocks);
+            debug!(target: "mover", "DEAD ON ROCKS");
         }
-
-        if xpos < rect.left() {
-            return 1;
-        }
-
-        if ypos < rect.bottom() {
-            return 1;
-        }
-
-        if ypos > rect.top() {
-            return 1;
-        }
-
-        //check against middle box
-        //hardwired at the moment.
-        if xpos >= -50.0 && xpos <= 50.0 && ypos <= 50.0 && ypos >= -50.0 {
-            return 1;
-        }
-        return 0;
     } //end of check_dead
 
-    pub fn get_sensor_data(&mut self, rect: Rect) {
-        //this looks stupid. Testing for intersection
-        //of lines would be better...maybe
-        //
-        let _knt = 0;
-        for isensor in 0..constants::NUM_SENSORS {
-            let mut sensor_ai: i32 = 99;
-            //let _dist = 0.0;
-
-            if isensor == 0 {
-                sensor_ai = self.angle_index as i32 + 1;
-            }
-            if isensor == 1 {
-                sensor_ai = self.angle_index as i32;
-            }
-            if isensor == 2 {
-                sensor_ai = self.angle_index as i32 - 1;
-            }
-            //adjust for outside range
-            if sensor_ai > constants::NUM_ANGLES as i32 - 1 {
-                sensor_ai = sensor_ai % constants::NUM_ANGLES as i32;
-            }
-            if sensor_ai < 0 {
-                sensor_ai = constants::NUM_ANGLES as i32 - 1;
-            }
-            let mut xpos = self.position.x;
-            let mut ypos = self.position.y;
-            for _step in 0..constants::SENSOR_LENGTH as u32 {
-                xpos = xpos + constants::ANGLES_DX[sensor_ai as usize];
-                ypos = ypos + constants::ANGLES_DY[sensor_ai as usize];
-                let hit = self.check_collisions(xpos, ypos, rect);
-                if hit == 1 {
-                    break;
-                }
-                let fdx = xpos - self.position.x;
-                let fdy = ypos - self.position.y;
-                if fdx.hypot(fdy) > constants::SENSOR_LENGTH {
-                    break;
-                }
-            } //end of loop on step
-
-            self.sensor_data[isensor][0] = xpos;
-            self.sensor_data[isensor][1] = ypos;
+    pub fn get_sensor_data(&mut self, arena: &Arena) {
+        //each antenna is a ray cast from the rover. its end is
+        //where it first touches something, or its full length out
+        let length = self.config.sensor_length;
+        let num_sensors = self.config.num_sensors;
+        for isensor in 0..num_sensors {
+            let turn = sensor_turn(isensor, num_sensors);
+            let sensor_ai = (self.angle_index as isize + turn)
+                .rem_euclid(constants::NUM_ANGLES as isize) as usize;
+            let dx = constants::ANGLES_DX[sensor_ai];
+            let dy = constants::ANGLES_DY[sensor_ai];
+            let distance = arena
+                .cast(self.position, pt2(dx, dy), length)
+                .map_or(length, |hit| hit.distance);
+            let scale = distance / dx.hypot(dy);
+            self.sensor_data[isensor][0] = self.position.x + dx * scale;
+            self.sensor_data[isensor][1] = self.position.y + dy * scale;
         } //end of sensor loop
     } //end of get_sensor_data
 
-    pub fn reset_mover(&mut self, width:f32,height:f32)  {
+    pub fn reset_mover<R: Rng + ?Sized>(&mut self, width: f32, height: f32, rng: &mut R) {
         self.brain.fitness = 0.0;
+        self.brain.objectives.clear();
         self.isdead = 0;
-        let start_x = width / 2.0 - constants::SENSOR_LENGTH + 10.0;
-        let start_y = (height / 2.0) - constants::SENSOR_LENGTH;
+        self.death = None;
+        self.turns = 0;
+        self.fitness.reset();
+        self.network.reset(&self.brain);
+        let start_x = width / 2.0 - self.config.sensor_length + 10.0;
+        let start_y = (height / 2.0) - self.config.sensor_length;
         self.position = pt2(start_x, start_y);
-        self.angle_index = random_range(0, constants::NUM_ANGLES);
+        self.angle_index = rng.gen_range(0..constants::NUM_ANGLES);
         self.multiplier = 1;
         self.velocity_x = constants::ANGLES_DX[self.angle_index];
         self.velocity_y = constants::ANGLES_DY[self.angle_index];
     }
-    pub fn mutate(&mut self) {
 
-        //start mutations here ...
+    //put the rover somewhere, heading along angle_index
+    pub fn place(&mut self, position: Point2, angle_index: usize) {
+        self.position = position;
+        self.angle_index = angle_index;
+        self.velocity_x = constants::ANGLES_DX[angle_index];
+        self.velocity_y = constants::ANGLES_DY[angle_index];
+    }
 
-        let mutidx = random_range(0, constants::NUM_NEURONS as usize);
-        if self.brain.xsign[mutidx] == 0 {
-            self.brain.xsign[mutidx] = 1;
-        } else {
-            self.brain.xsign[mutidx] = 0;
-        }
+    pub fn mutate<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.mutation.mutate(&mut self.brain, rng);
+    }
 
-        let mutidx = random_range(0, constants::NUM_NEURONS as usize);
-        let ilink = random_range(0, constants::NUM_NEURONS);
-        if self.brain.nconn[mutidx][ilink] == 0 {
-            self.brain.nconn[mutidx][ilink] = 1;
-        } else {
-            self.brain.nconn[mutidx][ilink] = 0;
+    //the 1/5th rule for the steady GA, as breed does it: once a
+    //population's worth of children have lived, tune the rates by
+    //how many of them beat their parent
+    pub fn adapt(&mut self, parent_fitness: f32) {
+        self.children += 1;
+        if self.brain.fitness > parent_fitness {
+            self.better_children += 1;
         }
-
-        //might not want to do this.
-        // lets keep all input signals
-        /*
-        let mutidx = random_range(0,constants::NUM_NEURONS as usize);
-        let ilink = random_range(0,constants::NUM_NEURONS);
-        if self.brain.iconn[mutidx][ilink] == 0 {
-            self.brain.iconn[mutidx][ilink] = 1;
-        } else {
-            self.brain.iconn[mutidx][ilink] = 0;
+        if self.children >= self.config.num_brains {
+            let success = self.better_children as f64 / self.children as f64;
+            self.mutation.adapt(success);
+            self.children = 0;
+            self.better_children = 0;
         }
-        */
+    }
+} //end of impl
 
-    } //end of mutate
+//one input line per neuron, as in the paper, and one output
+//group each for turn left, go straight and turn right.
+fn random_brain<R: Rng + ?Sized>(config: &SimConfig, rng: &mut R) -> Brain {
+    let brain = Brain::random(
+        config.num_neurons,
+        config.num_neurons,
+        constants::NUM_MOTORS,
+        rng,
+    )
+    .expect("SimConfig::validate checks the brain sizes");
+    let brain = match config.neuron_model {
+        NeuronKind::Izhikevich => brain.with_izhikevich(rng),
+        _ => brain,
+    };
+    if config.weighted {
+        brain.with_weights(config.weight_max, rng)
+    } else {
+        brain
+    }
+}
 
-} //end of impl
+//the per life changes picked in the config
+pub fn mutation(config: &SimConfig) -> MutationMethod {
+    let weight = WeightMutation {
+        sigma: config.weight_sigma,
+        max: config.weight_max,
+        reset_rate: config.weight_reset_rate,
+    };
+    match config.mutation {
+        MutationKind::Count => MutationMethod::Count(FlipMutation {
+            xsign: config.xsign_mutations,
+            nconn: config.nconn_mutations,
+            iconn: config.iconn_mutations,
+            izh: config.izh_mutations,
+            weights: config.weight_mutations,
+            weight,
+        }),
+        MutationKind::Rate => MutationMethod::Rate(RateMutation {
+            xsign: config.xsign_rate,
+            nconn: config.nconn_rate,
+            iconn: config.iconn_rate,
+            izh: config.izh_rate,
+            weights: config.weight_rate,
+            weight,
+            scale: 1.0,
+            adapt_factor: config.adapt_factor,
+        }),
+    }
+}
 
+//the fitness components with a weight above 0 in the config
+pub fn fitness(config: &SimConfig) -> Weighted {
+    let components = vec![
+        (
+            config.fitness_straight,
+            FitnessComponent::Straight(Straight::default()),
+        ),
+        (
+            config.fitness_floreano,
+            FitnessComponent::Floreano(Floreano::default()),
+        ),
+        (
+            config.fitness_survival,
+            FitnessComponent::Survival(Survival::default()),
+        ),
+        (
+            config.fitness_coverage,
+            FitnessComponent::Coverage(Coverage::new(config.coverage_cell)),
+        ),
+        (
+            config.fitness_clearance,
+            FitnessComponent::Clearance(Clearance::default()),
+        ),
+    ];
+    Weighted {
+        terms: components.into_iter().filter(|(w, _)| *w > 0.0).collect(),
+    }
+}
 
+//the neuron rule picked in the config, at rest
+fn network(config: &SimConfig, brain: &Brain) -> Network {
+    match config.neuron_model {
+        NeuronKind::Binary => {
+            let params = BinaryParams {
+                threshold: config.threshold,
+                threshold_noise: config.threshold_noise,
+                leaking_constant: config.leaking_constant,
+                refractory_period: config.refractory_period,
+                wiring: match config.wiring {
+                    WiringKind::Legacy => Wiring::Legacy,
+                    WiringKind::Presynaptic => Wiring::Presynaptic,
+                },
+            };
+            let model = BinaryModel {
+                params,
+                clear_membrane: !config.stateful,
+            };
+            Network::binary(model, brain)
+        }
+        NeuronKind::Lif => {
+            let model = LifModel {
+                tau: config.lif_tau,
+                v_rest: config.lif_v_rest,
+                v_reset: config.lif_v_reset,
+                threshold: config.lif_threshold,
+                refractory_period: config.lif_refractory_period,
+                input_weight: config.lif_input_weight,
+                recurrent_weight: config.lif_recurrent_weight,
+            };
+            Network::lif(model, brain)
+        }
+        NeuronKind::Izhikevich => {
+            let model = IzhikevichModel {
+                input_current: config.izh_input_current,
+                recurrent_current: config.izh_recurrent_current,
+            };
+            Network::izhikevich(model, brain)
+        }
+    }
+}
 
+//heading of sensor isensor, in 45 degree steps left (+) of the
+//rover's. fanned out from left to right, straight ahead only for
+//an odd number: 3 sensors are +1, 0, -1 and 4 are +2, +1, -1, -2
+fn sensor_turn(isensor: usize, num_sensors: usize) -> isize {
+    let half = (num_sensors / 2) as isize;
+    let i = isensor as isize;
+    if num_sensors % 2 == 1 || i < half {
+        half - i
+    } else {
+        half - 1 - i
+    }
+}
+
+//input lines for sensor isensor. Lines are shared out in
+//contiguous blocks, any spare lines go to the outer sensors.
+fn sensor_lines(isensor: usize, num_sensors: usize, num_inputs: usize) -> Range<usize> {
+    let base = num_inputs / num_sensors;
+    let spare = num_inputs % num_sensors;
+    let front = spare / 2;
+    let back = num_sensors - (spare - front);
+    let width = |i: usize| base + if i < front || i >= back { 1 } else { 0 };
+    let start: usize = (0..isensor).map(width).sum();
+    start..start + width(isensor)
+}
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn dies_on_wall_and_rocks() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let config = SimConfig::default();
+        let arena = Arena::new(&config);
+        let rect = arena.walls;
+        let mut mover = Mover::new(rect.right() + 1.0, 0.0, &config, &mut rng);
+        mover.check_dead(&arena);
+        assert_eq!(mover.isdead, 1);
+        assert_eq!(mover.death, Some(Death::Wall));
+        assert_eq!(mover.position.x, rect.right());
+
+        let mut mover = Mover::new(0.0, 0.0, &config, &mut rng);
+        mover.check_dead(&arena);
+        assert_eq!(mover.isdead, 1);
+        assert_eq!(mover.death, Some(Death::Rocks));
+
+        let mut mover = Mover::new(100.0, 100.0, &config, &mut rng);
+        mover.check_dead(&arena);
+        assert_eq!(mover.isdead, 0);
+    }
+
+    #[test]
+    fn sensors_end_on_what_they_see() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let config = SimConfig::default();
+        let arena = Arena::new(&config);
+        //heading -x at the box, its corner is just out of reach
+        let mut mover = Mover::new(100.0, 0.0, &config, &mut rng);
+        mover.place(pt2(100.0, 0.0), 4);
+        mover.get_sensor_data(&arena);
+        let reach = config.sensor_length / 2.0f32.sqrt();
+        let near = |a: [f32; 2], b: [f32; 2]| (a[0] - b[0]).hypot(a[1] - b[1]) < 1e-3;
+        assert!(near(mover.sensor_data[0], [100.0 - reach, -reach]));
+        assert!(near(mover.sensor_data[1], [50.0, 0.0]));
+        assert!(near(mover.sensor_data[2], [100.0 - reach, reach]));
+        mover.build_sensor_data_vector();
+        assert!((mover.proximity - (1.0 - 50.0 / config.sensor_length)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn sensors_fan_out_around_the_heading() {
+        let turns = |n: usize| (0..n).map(|i| sensor_turn(i, n)).collect::<Vec<_>>();
+        assert_eq!(turns(3), vec![1, 0, -1]);
+        assert_eq!(turns(1), vec![0]);
+        assert_eq!(turns(4), vec![2, 1, -1, -2]);
+        assert_eq!(turns(7), vec![3, 2, 1, 0, -1, -2, -3]);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        let config = SimConfig {
+            num_sensors: 5,
+            ..SimConfig::default()
+        };
+        let arena = Arena::new(&config);
+        let mut mover = Mover::new(100.0, 0.0, &config, &mut rng);
+        mover.place(pt2(100.0, 0.0), 4);
+        mover.get_sensor_data(&arena);
+        mover.think(&mut rng);
+        assert_eq!(mover.sensor_data.len(), 5);
+        //two steps right of west is north
+        let [x, y] = mover.sensor_data[4];
+        assert!((x - 100.0).abs() < 1e-3 && (y - config.sensor_length).abs() < 1e-3);
+    }
+
+    #[test]
+    fn no_bias_while_any_sensor_sees_something() {
+        //4 sensors on 8 lines, 2 each. heading north near the east
+        //wall only the two right hand ones (lines 4..8) see it
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        let config = SimConfig {
+            num_sensors: 4,
+            ..SimConfig::default()
+        };
+        let arena = Arena::new(&config);
+        let mut mover = Mover::new(180.0, 0.0, &config, &mut rng);
+        mover.place(pt2(180.0, 0.0), 2);
+        mover.get_sensor_data(&arena);
+        mover.build_sensor_data_vector();
+        let seen = mover.sensor_data_vector.clone();
+        assert!(seen[..4].iter().all(|&line| line == 0));
+        assert!(seen[4..].contains(&1));
+        mover.think(&mut rng);
+        assert_eq!(mover.sensor_data_vector, seen);
+    }
+
+    #[test]
+    fn sensor_lines_match_the_8_bit_brain() {
+        let lines: Vec<_> = (0..3).map(|i| sensor_lines(i, 3, 8)).collect();
+        assert_eq!(lines, vec![0..3, 3..5, 5..8]);
+        let lines: Vec<_> = (0..3).map(|i| sensor_lines(i, 3, 64)).collect();
+        assert_eq!(lines, vec![0..21, 21..42, 42..64]);
+    }
+
+    #[test]
+    fn different_brain_sizes_side_by_side() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let arena = Arena::new(&SimConfig::default());
+        let models = [NeuronKind::Binary, NeuronKind::Lif, NeuronKind::Izhikevich];
+        for (&num_neurons, &neuron_model) in [8, 16, 64].iter().zip(models.iter().cycle()) {
+            let config = SimConfig {
+                num_neurons,
+                neuron_model,
+                weighted: num_neurons > 8,
+                ..SimConfig::default()
+            };
+            let mut mover = Mover::new(120.0, 120.0, &config, &mut rng);
+            for _ in 0..20 {
+                mover.get_sensor_data(&arena);
+                mover.think(&mut rng);
+                mover.update_mover();
+            }
+            assert_eq!(mover.sensor_data_vector.len(), num_neurons);
+            assert_eq!(mover.brain.num_neurons, num_neurons);
+            mover.mutate(&mut rng);
+            assert!(mover.brain.validate().is_ok());
+        }
+    }
 }
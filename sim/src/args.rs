@@ -0,0 +1,126 @@
+//Command line options shared by the headless runner and the viewer.
+//
+//  [EPOCHS]        number of epochs (lives) for the headless runner
+//  --seed N        seed the simulation rng, same seed -> same run
+//  --config FILE   load a SimConfig from a .toml or .json file
+//  --set KEY=VAL   override one config value, can be repeated
+//  --resume FILE   carry on with a population saved by --save
+//  --save FILE     save the population when the run ends
+//                  (.json for JSON, anything else is binary)
+//  --restore FILE  carry on exactly from a checkpoint, which brings
+//                  its own config and seed. EPOCHS is then the
+//                  total the run stops at, checkpointed lives included
+//  --replay FILE   drive a brain saved with save_brain (or the best
+//                  of a population saved with --save), no evolution.
+//                  EPOCHS is then the number of lives
+//  --start X,Y[,A] where a replayed life starts, heading A (0..7)
+//                  or a random one. repeat it to go round several
+
+use crate::Start;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Args {
+    pub epochs: Option<usize>,
+    pub seed: Option<u64>,
+    pub config: Option<PathBuf>,
+    pub overrides: Vec<String>,
+    pub resume: Option<PathBuf>,
+    pub save: Option<PathBuf>,
+    pub restore: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub starts: Vec<Start>,
+}
+
+impl Args {
+    pub fn from_env() -> Result<Self, String> {
+        Args::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = args.next().ok_or("--seed needs a value")?;
+                    let seed = value
+                        .parse()
+                        .map_err(|_| format!("bad --seed value: {}", value))?;
+                    parsed.seed = Some(seed);
+                }
+                "--config" => {
+                    let value = args.next().ok_or("--config needs a file name")?;
+                    parsed.config = Some(PathBuf::from(value));
+                }
+                "--resume" => {
+                    let value = args.next().ok_or("--resume needs a file name")?;
+                    parsed.resume = Some(PathBuf::from(value));
+                }
+                "--restore" => {
+                    let value = args.next().ok_or("--restore needs a file name")?;
+                    parsed.restore = Some(PathBuf::from(value));
+                }
+                "--replay" => {
+                    let value = args.next().ok_or("--replay needs a file name")?;
+                    parsed.replay = Some(PathBuf::from(value));
+                }
+                "--start" => {
+                    let value = args.next().ok_or("--start needs x,y")?;
+                    parsed.starts.push(value.parse()?);
+                }
+                "--save" => {
+                    let value = args.next().ok_or("--save needs a file name")?;
+                    parsed.save = Some(PathBuf::from(value));
+                }
+                "--set" => {
+                    let value = args.next().ok_or("--set needs key=value")?;
+                    parsed.overrides.push(value);
+                }
+                _ if parsed.epochs.is_none() && !arg.starts_with('-') => {
+                    let epochs = arg
+                        .parse()
+                        .map_err(|_| format!("number of epochs must be a whole number: {}", arg))?;
+                    parsed.epochs = Some(epochs);
+                }
+                _ => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+        Ok(parsed)
+    }
+} //end of impl Args
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Args, String> {
+        Args::parse(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn parses_epochs_and_seed() {
+        let args = parse("500 --seed 42").unwrap();
+        assert_eq!(args.epochs, Some(500));
+        assert_eq!(args.seed, Some(42));
+        assert_eq!(parse("").unwrap(), Args::default());
+
+        let args = parse("--config run.toml --set num_brains=20 --set settling_time=5").unwrap();
+        assert_eq!(args.config, Some(PathBuf::from("run.toml")));
+        assert_eq!(args.overrides, vec!["num_brains=20", "settling_time=5"]);
+        let args = parse("--resume pop.bin --save pop.json").unwrap();
+        assert_eq!(args.resume, Some(PathBuf::from("pop.bin")));
+        assert_eq!(args.save, Some(PathBuf::from("pop.json")));
+        assert!(parse("--resume").is_err());
+        let args = parse("100 --restore run.ckpt").unwrap();
+        assert_eq!(args.restore, Some(PathBuf::from("run.ckpt")));
+        let args = parse("3 --replay best.json --start 100,100 --start -100,100,4").unwrap();
+        assert_eq!(args.replay, Some(PathBuf::from("best.json")));
+        assert_eq!(args.starts.len(), 2);
+        assert_eq!(args.starts[1].angle_index, Some(4));
+        assert!(parse("--start 100").is_err());
+        assert!(parse("--seed").is_err());
+        assert!(parse("--seed x").is_err());
+        assert!(parse("--bogus").is_err());
+    }
+}
@@ -0,0 +1,855 @@
+//The evolutionary loop, without any graphics.
+//
+//This used to live in the nannou update callback in main.
+//Now main is just a viewer that calls step() once a frame
+//and the headless runner calls run_epochs() as fast as it can.
+
+extern crate brain;
+extern crate constants;
+extern crate evolution;
+extern crate mover;
+use brain::Brain;
+use constants::{CrossoverKind, GaKind, SelectionKind, SimConfig};
+use evolution::*;
+use log::{debug, error, info, trace};
+use mover::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+mod args;
+mod metrics;
+mod replay;
+mod trials;
+pub use args::Args;
+pub use metrics::{GenerationRecord, LifeRecord, Metrics, Record};
+pub use replay::{Replay, Start};
+pub use trials::{aggregate, TrialScore};
+
+//ChaCha8 rather than StdRng: its output is fixed across rand
+//releases and platforms, so a seed means the same run everywhere.
+pub type SimRng = ChaCha8Rng;
+
+//RUST_LOG picks what is shown, targets are brain, mover and ga,
+//e.g. RUST_LOG=mover=debug for every death or RUST_LOG=ga=trace
+//for every fitness. Without it the seed, the generations,
+//checkpoints and errors are all there is.
+pub fn init_logging() {
+    let env = env_logger::Env::default().default_filter_or("warn,sim=info,main=info,ga=info");
+    env_logger::Builder::from_env(env)
+        .format_timestamp(None)
+        .target(env_logger::Target::Stdout)
+        .init();
+}
+
+//lives a --replay runs when no number is given
+pub const REPLAY_LIVES: usize = 10;
+
+//everything a run needs to carry on, so a checkpoint is just
+//the whole Simulation written out
+#[derive(Serialize, Deserialize)]
+pub struct Simulation {
+    pub mover: Mover,
+    pub arena: Arena,
+    pub loop_knt: usize,
+    pub num_epochs: usize,
+    pub seed: u64,
+    pub rng: SimRng,
+    pub config: SimConfig,
+    pub ga: Generational<SelectionMethod, CrossoverMethod, MutationMethod>, //ga = "generational" only
+    pub replay: Option<Replay>, //--replay, one saved brain and no GA
+    pub trial: usize,           //of the brain living now, 0..config.trials
+    pub trial_scores: Vec<TrialScore>,
+    #[serde(skip)]
+    pub metrics: Option<Metrics>, //open while metrics_path is written
+}
+
+impl Simulation {
+    //same seed, same run -- every random choice comes from self.rng
+    pub fn new(config: SimConfig, seed: u64) -> Self {
+        let mut rng = SimRng::seed_from_u64(seed);
+        let arena = Arena::new(&config);
+        let rect = arena.walls;
+        let start_x = rect.w() / 2.0 - config.sensor_length + 10.0;
+        let start_y = (rect.h() / 2.0) - config.sensor_length;
+
+        let mut mover = Mover::new(start_x, start_y, &config, &mut rng);
+        if config.ga == GaKind::Generational {
+            //work through the population in order
+            mover.brain_index = 0;
+            mover.brain = mover.population.brains[0].clone();
+            mover.network.reset(&mover.brain);
+        }
+        let ga = Generational {
+            selection: selection(&config),
+            crossover: crossover(&config),
+            mutation: mover::mutation(&config),
+            crossover_rate: config.crossover_rate,
+            elitism: config.elitism,
+        };
+        let loop_knt = 0;
+        let num_epochs = 0;
+        let mut sim = Simulation {
+            mover,
+            arena,
+            loop_knt,
+            num_epochs,
+            seed,
+            rng,
+            config,
+            ga,
+            replay: None,
+            trial: 0,
+            trial_scores: Vec::new(),
+            metrics: None,
+        };
+        sim.start_trial();
+        sim
+    }
+
+    //config from --config plus any --set overrides.
+    //seed from --seed if given, otherwise pick one and say so
+    //that an interesting run can be repeated.
+    pub fn from_args(args: &Args) -> Result<Self, String> {
+        if let Some(path) = &args.restore {
+            if args.config.is_some() || !args.overrides.is_empty() || args.seed.is_some() {
+                return Err("--restore brings its own config and seed".to_string());
+            }
+            if args.replay.is_some() {
+                return Err("--restore and --replay do not mix".to_string());
+            }
+            let mut sim = Simulation::restore(path)?;
+            info!("RESTORED: {} AT EPOCH {}", path.display(), sim.num_epochs);
+            sim.open_metrics(true)?;
+            return Ok(sim);
+        }
+        let mut config = match &args.config {
+            Some(path) => SimConfig::load(path)?,
+            None => SimConfig::default(),
+        };
+        config.set_all(&args.overrides)?;
+        config.load_map()?;
+        let seed = args.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        info!("SEED: {}", seed);
+        if let Some(path) = &args.replay {
+            if args.resume.is_some() || args.save.is_some() {
+                return Err("--replay does not evolve, so no --resume or --save".to_string());
+            }
+            let replay = Replay {
+                starts: args.starts.clone(),
+                lives: args.epochs.unwrap_or(REPLAY_LIVES),
+            };
+            let mut sim = Simulation::replay(config, seed, champion(path)?, replay)?;
+            info!("REPLAY: {}", path.display());
+            sim.open_metrics(false)?;
+            return Ok(sim);
+        }
+        if !args.starts.is_empty() {
+            return Err("--start is only for --replay".to_string());
+        }
+        let mut sim = Simulation::new(config, seed);
+        if let Some(path) = &args.resume {
+            sim.resume(load_population(path)?)?;
+            info!("RESUMED: {}", path.display());
+        }
+        sim.open_metrics(args.resume.is_some())?;
+        Ok(sim)
+    }
+
+    //carry on with a saved population: its brains, fitness, lives
+    //and generation count, its hall of fame and how far the 1/5th
+    //rule had tuned the rates. the GA settings come from the
+    //config, so they can be changed between runs, the hall of fame
+    //size included. a brain that was part way through its trials
+    //starts them again
+    pub fn resume(&mut self, population: Population) -> Result<(), String> {
+        self.check_brain(&population.brains[0])?;
+        if population.len() != self.config.num_brains {
+            return Err(format!(
+                "the saved population has {} brains, the config asks for {}",
+                population.len(),
+                self.config.num_brains
+            ));
+        }
+        //files from before evaluations was kept only have the lives
+        let evaluations = if population.evaluations > 0 {
+            population.evaluations
+        } else {
+            population.lives / self.config.trials
+        };
+        let m = &mut self.mover;
+        m.population = population;
+        m.brain_index = 0;
+        m.brain = m.population.brains[0].clone();
+        m.brain.fitness = 0.0;
+        m.network.reset(&m.brain);
+        m.population.evaluations = evaluations;
+        m.population.hall_of_fame.resize(self.config.hall_of_fame);
+        //fixed rates stay as the config has them
+        if self.config.adapt_factor < 1.0 {
+            let adaptation = &m.population.adaptation;
+            m.mutation.set_scale(adaptation.scale);
+            m.children = adaptation.children;
+            m.better_children = adaptation.better;
+            self.ga.mutation.set_scale(adaptation.scale);
+        }
+        self.num_epochs = evaluations * self.config.trials;
+        m.population.lives = self.num_epochs;
+        self.loop_knt = 0;
+        self.trial = 0;
+        self.trial_scores.clear();
+        Ok(())
+    }
+
+    //start writing to metrics_path, if the config has one.
+    //append to carry on the file of an earlier run
+    pub fn open_metrics(&mut self, append: bool) -> Result<(), String> {
+        self.metrics = match &self.config.metrics_path {
+            Some(path) => Some(Metrics::open(path, append)?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    fn write_metrics(&mut self, record: Record) {
+        if let Some(metrics) = &mut self.metrics {
+            if let Err(err) = metrics.write(&record) {
+                error!("METRICS: {}", err);
+            }
+        }
+    }
+
+    //a saved brain has to fit the rover of this config
+    fn check_brain(&self, brain: &Brain) -> Result<(), String> {
+        if brain.num_neurons != self.config.num_neurons
+            || brain.num_inputs != self.config.num_neurons
+        {
+            return Err(format!(
+                "saved brains have {} neurons, the config asks for {}",
+                brain.num_neurons, self.config.num_neurons
+            ));
+        }
+        Ok(())
+    }
+
+    //the whole run, written atomically
+    pub fn checkpoint<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), String> {
+        save(self, path)
+    }
+
+    //a run exactly as checkpointed, rng and all
+    pub fn restore<P: AsRef<std::path::Path>>(path: P) -> Result<Self, String> {
+        let sim: Simulation = load(path)?;
+        sim.config.validate()?;
+        let m = &sim.mover;
+        for brain in m.population.brains.iter().chain(std::iter::once(&m.brain)) {
+            brain.validate()?;
+        }
+        Ok(sim)
+    }
+
+    //generations so far. the steady GA has none, every num_brains
+    //lives count as one
+    pub fn generation(&self) -> usize {
+        match self.config.ga {
+            GaKind::Steady => self.num_epochs / (self.config.num_brains * self.config.trials),
+            GaKind::Generational => self.mover.population.generation,
+        }
+    }
+
+    //with the state of the 1/5th rule, so --resume carries it on
+    pub fn save_population<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), String> {
+        let m = &self.mover;
+        let mut population = m.population.clone();
+        population.adaptation = match self.config.ga {
+            GaKind::Steady => Adaptation {
+                scale: m.mutation.scale(),
+                children: m.children,
+                better: m.better_children,
+            },
+            GaKind::Generational => Adaptation {
+                scale: self.ga.mutation.scale(),
+                ..Adaptation::default()
+            },
+        };
+        save_population(&population, path)
+    }
+
+    //one tick of the rover's life.
+    //returns true when the tick ended a life (and started an epoch)
+    pub fn step(&mut self) -> bool {
+        self.mover.check_dead(&self.arena);
+        if self.mover.isdead == 0 {
+            self.mover.get_sensor_data(&self.arena);
+            self.mover.think(&mut self.rng);
+            self.mover.update_mover();
+            self.loop_knt += 1;
+        }
+
+        if self.mover.isdead == 1 || self.loop_knt > self.config.max_loop_knt {
+            self.end_of_life();
+            return true;
+        }
+        false
+    } //end of step
+
+    //keep stepping until n more lives are over
+    pub fn run_epochs(&mut self, n: usize) {
+        self.run_to(self.num_epochs + n);
+    }
+
+    //keep stepping until total lives are over, counting those
+    //before a --restore
+    pub fn run_to(&mut self, total: usize) {
+        while self.num_epochs < total {
+            self.step();
+        }
+    }
+
+    fn end_of_life(&mut self) {
+        let life = LifeRecord {
+            life: self.num_epochs + 1,
+            generation: self.generation(),
+            brain: self.mover.brain_index,
+            trial: self.trial,
+            fitness: self.mover.brain.fitness,
+            steps: self.loop_knt,
+            turns: self.mover.turns,
+            death: self.mover.death.unwrap_or(Death::Timeout),
+        };
+        self.write_metrics(Record::Life(life));
+        if self.replay.is_some() {
+            self.end_of_replay_life();
+            return;
+        }
+        if self.trial + 1 < self.config.trials {
+            self.next_trial();
+            self.num_epochs += 1;
+            self.mover.population.lives = self.num_epochs;
+            return;
+        }
+        self.score_trials();
+        self.mover.population.evaluations += 1;
+        let generation = self.generation();
+        debug!(target: "ga", "END OF LIFE FITNESS WAS: {}", self.mover.brain.fitness);
+        match self.config.ga {
+            GaKind::Steady => {
+                //no generations here, a brain is filed under its life
+                let m = &mut self.mover;
+                if m.population.hall_of_fame.offer(&m.brain, self.num_epochs) {
+                    self.export_hall_of_fame();
+                }
+                self.steady_state();
+            }
+            GaKind::Generational => self.generational(),
+        }
+
+        self.loop_knt = 0;
+        self.mover
+            .reset_mover(self.arena.walls.w(), self.arena.walls.h(), &mut self.rng);
+        self.start_trial();
+
+        self.num_epochs += 1;
+        self.mover.population.lives = self.num_epochs;
+        debug!(target: "ga", "NUM EPOCHS: {} ", self.num_epochs);
+
+        if self.generation() == generation {
+            return;
+        }
+        self.write_generation_metrics(generation);
+
+        //at the end of a life, so a restored run starts a fresh one.
+        //checked_rem is None for every = 0, which turns them off
+        let every = self.config.checkpoint_every;
+        if self.generation().checked_rem(every) == Some(0) {
+            if let Some(path) = &self.config.checkpoint_path {
+                match self.checkpoint(path) {
+                    Ok(()) => info!("CHECKPOINT: {}", path.display()),
+                    Err(err) => error!("CHECKPOINT: {}", err),
+                }
+            }
+        }
+    } //end of end_of_life
+
+    //next brain in the population. once all of them have lived,
+    //breed the next generation and start again from the first
+    fn generational(&mut self) {
+        let m = &mut self.mover;
+        let pop = &mut m.population;
+        pop.brains[m.brain_index] = m.brain.clone();
+        m.brain_index += 1;
+        if m.brain_index == pop.len() {
+            if let Some(path) = &self.config.pareto_path {
+                if let Err(err) =
+                    export_front(&pop.brains, &m.fitness.names(), pop.generation, path)
+                {
+                    error!(target: "ga", "PARETO FRONT: {}", err);
+                }
+            }
+            let stats = self.ga.breed(pop, &mut self.rng);
+            info!(
+                target: "ga",
+                "GENERATION: {} BEST: {} MEAN: {:.1} WORST: {} STD DEV: {:.1}",
+                stats.generation, stats.best, stats.mean, stats.worst, stats.std_dev
+            );
+            m.brain_index = 0;
+            m.brain = pop.brains[0].clone();
+            self.export_hall_of_fame();
+        } else {
+            m.brain = pop.brains[m.brain_index].clone();
+        }
+    }
+
+    //the generation that just ended. the generational GA has bred
+    //already, so its stats come from the history
+    fn write_generation_metrics(&mut self, generation: usize) {
+        if self.metrics.is_none() {
+            return;
+        }
+        let pop = &self.mover.population;
+        let stats = match self.config.ga {
+            GaKind::Steady => GenerationStats::new(generation, &pop.fitness()),
+            GaKind::Generational => pop.history.last().cloned().unwrap_or_default(),
+        };
+        let record = GenerationRecord {
+            generation,
+            lives: self.num_epochs,
+            best: stats.best,
+            mean: stats.mean,
+            worst: stats.worst,
+            std_dev: stats.std_dev,
+            diversity: pop.diversity(),
+        };
+        self.write_metrics(Record::Generation(record));
+    }
+
+    //best brains ever, if the config names a file for them
+    fn export_hall_of_fame(&self) {
+        if let Some(path) = &self.config.hall_of_fame_path {
+            if let Err(err) = self.mover.population.hall_of_fame.export(path) {
+                error!(target: "ga", "HALL OF FAME: {}", err);
+            }
+        }
+    }
+
+    //the original GA, run after every life
+    fn steady_state(&mut self) {
+        //do mutations and updates here
+        //
+        let m = &mut self.mover;
+        //get fitnesses for the population before choosing
+        //who to breed/mutate.
+        //
+        //brains scored so far, this one included
+        let evaluations = self.num_epochs / self.config.trials;
+        if evaluations < self.config.num_brains {
+            //store old results
+            m.population.brains[m.brain_index] = m.brain.clone();
+            //get new brain
+            m.brain_index = evaluations;
+            m.brain = m.population.brains[m.brain_index].clone();
+        } else {
+            //the brain that just lived is a mutated copy of
+            //brains[brain_index], which is untouched since. not so
+            //for the first one, its parent was never scored
+            if evaluations > self.config.num_brains {
+                let parent_fitness = m.population.brains[m.brain_index].fitness;
+                m.adapt(parent_fitness);
+            }
+            //don't want to do this sort but it makes things cleaner.
+            //use the technique in the paper next time.
+
+            //best first, a NaN fitness last
+            m.population
+                .brains
+                .sort_by(|d2, d1| compare(d1.fitness, d2.fitness));
+            for (ix, brain) in m.population.brains.iter().enumerate() {
+                trace!(target: "ga", "IX: {} FITNESS: {} ", ix, brain.fitness as u32);
+            }
+
+            //pick a new brain, fitness proportional. Roulette copes
+            //with NaN and with nobody having scored anything
+            let fitness = m.population.fitness();
+            let goal_index = Roulette.select(&fitness, &mut self.rng);
+            //before replacing brain , see if it should be stored in the
+            //population.
+            let worst = m.population.brains.len() - 1;
+            if compare(m.brain.fitness, m.population.brains[worst].fitness) != Ordering::Less {
+                m.population.brains[worst] = m.brain.clone();
+            }
+            m.brain_index = goal_index;
+            m.brain = m.population.brains[m.brain_index].clone();
+        } //end of if-else
+
+        m.mutate(&mut self.rng);
+    } //end of steady_state
+} //end of impl Simulation
+
+//a brain saved with save_brain, or the best one a population
+//saved with --save has ever seen
+fn champion(path: &std::path::Path) -> Result<Brain, String> {
+    let err = match load_brain(path) {
+        Ok(brain) => return Ok(brain),
+        Err(err) => err,
+    };
+    let population = load_population(path).map_err(|_| err)?;
+    match population.hall_of_fame.best() {
+        Some(entry) => Ok(entry.brain.clone()),
+        None => Err(format!("{} has an empty hall of fame", path.display())),
+    }
+}
+
+//the parent picking picked in the config
+fn selection(config: &SimConfig) -> SelectionMethod {
+    match config.selection {
+        SelectionKind::Roulette => SelectionMethod::Roulette(Roulette),
+        SelectionKind::Tournament => SelectionMethod::Tournament(Tournament {
+            k: config.tournament_size,
+        }),
+        SelectionKind::Rank => SelectionMethod::LinearRank(LinearRank {
+            pressure: config.rank_pressure,
+        }),
+        SelectionKind::Truncation => SelectionMethod::Truncation(Truncation {
+            fraction: config.truncation,
+        }),
+        SelectionKind::Sus => SelectionMethod::StochasticUniversal(StochasticUniversal),
+        SelectionKind::Nsga2 => SelectionMethod::Nsga2(Nsga2),
+    }
+}
+
+//the parent mixing picked in the config
+fn crossover(config: &SimConfig) -> CrossoverMethod {
+    match config.crossover {
+        CrossoverKind::None => CrossoverMethod::None(NoCrossover),
+        CrossoverKind::Uniform => CrossoverMethod::Uniform(UniformCrossover),
+        CrossoverKind::Neuron => CrossoverMethod::Neuron(NeuronCrossover),
+        CrossoverKind::OnePoint => CrossoverMethod::OnePoint(OnePointCrossover),
+        CrossoverKind::TwoPoint => CrossoverMethod::TwoPoint(TwoPointCrossover),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_epochs_counts_lives() {
+        let config = SimConfig {
+            num_brains: 4,
+            ..SimConfig::default()
+        };
+        let mut sim = Simulation::new(config, 7);
+        sim.run_epochs(4 + 3);
+        assert_eq!(sim.num_epochs, 4 + 3);
+        assert_eq!(sim.mover.population.brains.len(), 4);
+        sim.run_to(9);
+        assert_eq!(sim.num_epochs, 9);
+        sim.run_to(5);
+        assert_eq!(sim.num_epochs, 9);
+    }
+
+    #[test]
+    fn same_seed_same_fitness_trace() {
+        let trace = |seed| {
+            let mut sim = Simulation::new(SimConfig::default(), seed);
+            let mut fitness = Vec::new();
+            for _ in 0..constants::NUM_BRAINS + 2 {
+                sim.run_epochs(1);
+                fitness.extend(
+                    sim.mover
+                        .population
+                        .brains
+                        .iter()
+                        .map(|b| b.fitness.to_bits()),
+                );
+            }
+            fitness
+        };
+        assert_eq!(trace(42), trace(42));
+        assert_ne!(trace(42), trace(43));
+    }
+
+    #[test]
+    fn generational_ga_breeds_after_every_brain_lived() {
+        let config = SimConfig {
+            num_brains: 3,
+            ga: GaKind::Generational,
+            max_loop_knt: 50,
+            ..SimConfig::default()
+        };
+        let mut sim = Simulation::new(config, 7);
+        sim.run_epochs(2);
+        assert_eq!(sim.mover.brain_index, 2);
+        assert_eq!(sim.mover.population.generation, 0);
+        sim.run_epochs(1 + 3);
+        assert_eq!(sim.mover.brain_index, 0);
+        assert_eq!(sim.mover.population.generation, 2);
+        assert_eq!(sim.mover.population.history.len(), 2);
+    }
+
+    #[test]
+    fn metrics_has_a_row_per_life_and_generation() {
+        for &(ga, ext) in &[(GaKind::Steady, "csv"), (GaKind::Generational, "jsonl")] {
+            let path = std::env::temp_dir().join(format!("metrics_{}.{}", std::process::id(), ext));
+            let config = SimConfig {
+                num_brains: 3,
+                ga,
+                max_loop_knt: 50,
+                metrics_path: Some(path.clone()),
+                ..SimConfig::default()
+            };
+            let mut sim = Simulation::new(config, 7);
+            sim.open_metrics(false).unwrap();
+            sim.run_epochs(7);
+            sim.metrics = None;
+            let text = std::fs::read_to_string(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            let rows = |kind: &str| -> Vec<&str> {
+                let json = format!(r#"{{"record":"{}","#, kind);
+                let csv = format!("{},", kind);
+                text.lines()
+                    .filter(|l| l.starts_with(&json) || l.starts_with(&csv))
+                    .collect()
+            };
+            assert_eq!(rows("life").len(), 7);
+            assert_eq!(rows("generation").len(), 2);
+            let deaths = ["wall", "rocks", "timeout"];
+            assert!(rows("life")
+                .iter()
+                .all(|l| deaths.iter().any(|d| l.contains(d))));
+        }
+    }
+
+    #[test]
+    fn every_brain_lives_all_its_trials() {
+        let config = SimConfig {
+            num_brains: 2,
+            ga: GaKind::Generational,
+            max_loop_knt: 50,
+            trials: 3,
+            start_positions: vec![[150.0, 150.0], [-150.0, 150.0]],
+            start_headings: vec![2],
+            aggregate: constants::AggregateKind::Min,
+            ..SimConfig::default()
+        };
+        let mut sim = Simulation::new(config, 7);
+        assert_eq!(sim.mover.position, pt2(150.0, 150.0));
+        sim.run_epochs(4);
+        assert_eq!((sim.mover.brain_index, sim.trial), (1, 1));
+        assert_eq!(sim.mover.position, pt2(-150.0, 150.0));
+        assert_eq!(sim.mover.angle_index, 2);
+        let scores: Vec<f32> = sim.trial_scores.iter().map(|(f, _)| *f).collect();
+        sim.run_epochs(2);
+        assert_eq!(sim.mover.population.generation, 1);
+        assert_eq!(sim.trial, 0);
+        //the population is bred already, its stats went to the history
+        let worst = sim.mover.population.history[0].worst;
+        assert!(worst <= scores.iter().copied().fold(f32::INFINITY, f32::min));
+
+        for &start in &[
+            constants::StartKind::Random,
+            constants::StartKind::Stratified,
+        ] {
+            let config = SimConfig {
+                trials: 4,
+                start,
+                max_loop_knt: 20,
+                ..SimConfig::default()
+            };
+            let mut sim = Simulation::new(config, 3);
+            for _ in 0..8 {
+                assert!(sim.arena.walls.contains(sim.mover.position));
+                assert_eq!(sim.arena.hit(sim.mover.position), None);
+                sim.run_epochs(1);
+            }
+            assert_eq!(sim.generation(), 0);
+        }
+    }
+
+    #[test]
+    fn steady_state_copes_with_nan_and_zero_fitness() {
+        let config = SimConfig {
+            num_brains: 4,
+            ..SimConfig::default()
+        };
+        let mut sim = Simulation::new(config, 4);
+        sim.num_epochs = 4;
+        let mut picks = std::collections::BTreeSet::new();
+        for _ in 0..40 {
+            let fitness = [f32::NAN, 0.0, 0.0, 0.0];
+            for (brain, &f) in sim.mover.population.brains.iter_mut().zip(&fitness) {
+                brain.fitness = f;
+            }
+            sim.mover.brain.fitness = 0.0;
+            sim.steady_state();
+            //the NaN brain went last and the one that lived took its place
+            assert!(sim
+                .mover
+                .population
+                .brains
+                .iter()
+                .all(|b| !b.fitness.is_nan()));
+            picks.insert(sim.mover.brain_index);
+        }
+        assert!(picks.len() > 1, "{:?}", picks);
+    }
+
+    #[test]
+    fn steady_ga_adapts_its_rates() {
+        let config = SimConfig {
+            num_brains: 3,
+            max_loop_knt: 30,
+            mutation: constants::MutationKind::Rate,
+            adapt_factor: 0.5,
+            ..SimConfig::default()
+        };
+        let mut sim = Simulation::new(config, 2);
+        sim.run_epochs(3 + 3 * 4);
+        match &sim.mover.mutation {
+            MutationMethod::Rate(rate) => assert_ne!(rate.scale, 1.0),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn resumed_run_carries_on() {
+        let config = SimConfig {
+            num_brains: 3,
+            ga: GaKind::Generational,
+            max_loop_knt: 50,
+            ..SimConfig::default()
+        };
+        let mut sim = Simulation::new(config.clone(), 7);
+        sim.run_epochs(7);
+        let path = std::env::temp_dir().join(format!("resume_{}.bin", std::process::id()));
+        sim.save_population(&path).unwrap();
+
+        let mut resumed = Simulation::new(config.clone(), 8);
+        resumed.resume(load_population(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.num_epochs, 7);
+        assert_eq!(resumed.mover.population.generation, 2);
+        assert!(resumed
+            .mover
+            .brain
+            .same_genome(&sim.mover.population.brains[0]));
+        //the half lived generation starts over
+        resumed.run_epochs(3);
+        assert_eq!(resumed.mover.population.generation, 3);
+
+        let small = SimConfig {
+            num_neurons: 16,
+            ..SimConfig::default()
+        };
+        let population = resumed.mover.population.clone();
+        assert!(Simulation::new(small, 1)
+            .resume(population.clone())
+            .is_err());
+        let more = SimConfig {
+            num_brains: 4,
+            ..config
+        };
+        assert!(Simulation::new(more, 1).resume(population).is_err());
+    }
+
+    #[test]
+    fn resume_keeps_the_tuned_rates() {
+        let config = SimConfig {
+            num_brains: 3,
+            max_loop_knt: 30,
+            mutation: constants::MutationKind::Rate,
+            adapt_factor: 0.5,
+            ..SimConfig::default()
+        };
+        let mut sim = Simulation::new(config.clone(), 2);
+        sim.run_epochs(3 + 3 * 4 + 1);
+        let scale = sim.mover.mutation.scale();
+        assert_ne!(scale, 1.0);
+        assert!(sim.mover.population.hall_of_fame.len() > 2);
+        let path = std::env::temp_dir().join(format!("rates_{}.json", std::process::id()));
+        sim.save_population(&path).unwrap();
+
+        let smaller = SimConfig {
+            hall_of_fame: 2,
+            ..config
+        };
+        let mut resumed = Simulation::new(smaller.clone(), 3);
+        resumed.resume(load_population(&path).unwrap()).unwrap();
+        assert_eq!(resumed.mover.mutation.scale(), scale);
+        assert_eq!(resumed.mover.children, sim.mover.children);
+        assert_eq!(resumed.mover.better_children, sim.mover.better_children);
+        let hof = &resumed.mover.population.hall_of_fame;
+        assert_eq!((hof.capacity, hof.len()), (2, 2));
+
+        //fixed rates ignore what was saved
+        let fixed = SimConfig {
+            adapt_factor: 1.0,
+            ..smaller
+        };
+        let mut resumed = Simulation::new(fixed, 3);
+        resumed.resume(load_population(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.mover.mutation.scale(), 1.0);
+    }
+
+    #[test]
+    fn resume_counts_brains_not_lives() {
+        //two brains of the steady GA's first round scored over two
+        //trials each, then resumed with one trial per brain
+        let config = SimConfig {
+            num_brains: 4,
+            trials: 2,
+            max_loop_knt: 50,
+            ..SimConfig::default()
+        };
+        let mut sim = Simulation::new(config.clone(), 3);
+        sim.run_epochs(5);
+        assert_eq!(sim.mover.population.evaluations, 2);
+
+        let single = SimConfig {
+            trials: 1,
+            ..config
+        };
+        let mut resumed = Simulation::new(single, 4);
+        resumed.resume(sim.mover.population.clone()).unwrap();
+        assert_eq!(resumed.num_epochs, 2);
+        assert_eq!(resumed.generation(), 0);
+        resumed.run_epochs(2);
+        assert_eq!(resumed.mover.population.evaluations, 4);
+        assert_eq!(resumed.mover.brain_index, 3);
+    }
+
+    #[test]
+    fn restored_checkpoint_carries_on_bit_for_bit() {
+        for &ga in &[GaKind::Steady, GaKind::Generational] {
+            let path =
+                std::env::temp_dir().join(format!("ckpt_{}_{:?}.bin", std::process::id(), ga));
+            let config = SimConfig {
+                num_brains: 3,
+                ga,
+                max_loop_knt: 50,
+                checkpoint_every: 2,
+                checkpoint_path: Some(path.clone()),
+                ..SimConfig::default()
+            };
+            let mut sim = Simulation::new(config, 11);
+            //checkpoints after life 6, the end of generation 2
+            sim.run_epochs(7);
+            let mut restored = Simulation::restore(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(restored.num_epochs, 6);
+
+            sim.run_epochs(4);
+            restored.run_epochs(5);
+            assert_eq!(restored.num_epochs, sim.num_epochs);
+            assert!(restored.rng == sim.rng);
+            let bits = |sim: &Simulation| -> Vec<u32> {
+                let pop = &sim.mover.population;
+                pop.brains.iter().map(|b| b.fitness.to_bits()).collect()
+            };
+            assert_eq!(bits(&restored), bits(&sim));
+            assert!(restored.mover.brain.same_genome(&sim.mover.brain));
+        }
+    }
+}
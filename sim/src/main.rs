@@ -0,0 +1,42 @@
+//Headless runner -- no window, no GPU.
+//
+//cargo run --release -p sim -- 5000 --seed 42
+//runs 5000 epochs (lives) and exits.
+//add --save pop.bin to keep the population and --resume pop.bin
+//to carry on from it later.
+//--restore run.ckpt carries on until EPOCHS lives in all, so the
+//command of the original run finishes where it would have.
+//--replay best.json drives one saved brain for EPOCHS lives
+//(10 if not given) without evolving it.
+//RUST_LOG=ga=debug shows every life, see sim::init_logging.
+
+extern crate sim;
+use log::info;
+use sim::{Args, Simulation};
+
+fn main() {
+    sim::init_logging();
+    let args = Args::from_env().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(2);
+    });
+
+    let mut sim = Simulation::from_args(&args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(2);
+    });
+    let epochs = args.epochs.unwrap_or(1000);
+    match &sim.replay {
+        Some(replay) => sim.run_epochs(replay.lives),
+        None if args.restore.is_some() => sim.run_to(epochs),
+        None => sim.run_epochs(epochs),
+    }
+
+    if let Some(path) = &args.save {
+        sim.save_population(path).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
+        info!("SAVED: {}", path.display());
+    }
+}
@@ -0,0 +1,150 @@
+//Metrics -- a row per life and per generation, for plotting
+//learning curves with whatever tools you like.
+//
+//CSV or JSON Lines, by the extension of metrics_path. Both kinds
+//of row go to the same file and the record column tells them
+//apart. In CSV the cells that don't apply to a row are empty.
+
+use mover::Death;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LifeRecord {
+    pub life: usize, //counted from 1
+    pub generation: usize,
+    pub brain: usize, //index in the population
+    pub trial: usize, //of the brain, from 0
+    pub fitness: f32,
+    pub steps: usize,
+    pub turns: usize,
+    pub death: Death,
+}
+
+//fitness of the population once a generation is over, and how
+//far apart its genomes are
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct GenerationRecord {
+    pub generation: usize,
+    pub lives: usize, //so far, to line up with the life rows
+    pub best: f32,
+    pub mean: f32,
+    pub worst: f32,
+    pub std_dev: f32,
+    pub diversity: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "record", rename_all = "lowercase")]
+pub enum Record {
+    Life(LifeRecord),
+    Generation(GenerationRecord),
+}
+
+const CSV_HEADER: &str =
+    "record,life,generation,brain,trial,fitness,steps,turns,death,best,mean,worst,std_dev,diversity";
+
+impl Record {
+    fn csv(&self) -> String {
+        match self {
+            Record::Life(r) => {
+                let death = match r.death {
+                    Death::Wall => "wall",
+                    Death::Rocks => "rocks",
+                    Death::Timeout => "timeout",
+                };
+                format!(
+                    "life,{},{},{},{},{},{},{},{},,,,,",
+                    r.life, r.generation, r.brain, r.trial, r.fitness, r.steps, r.turns, death
+                )
+            }
+            Record::Generation(r) => format!(
+                "generation,{},{},,,,,,,{},{},{},{},{}",
+                r.lives, r.generation, r.best, r.mean, r.worst, r.std_dev, r.diversity
+            ),
+        }
+    }
+}
+
+pub struct Metrics {
+    out: BufWriter<File>,
+    csv: bool,
+}
+
+impl Metrics {
+    //append carries on the file of a resumed or restored run,
+    //otherwise it is started afresh
+    pub fn open<P: AsRef<Path>>(path: P, append: bool) -> Result<Self, String> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .write(!append)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .map_err(|err| format!("can't write {}: {}", path.display(), err))?;
+        let empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+        let csv = path.extension().and_then(|ext| ext.to_str()) == Some("csv");
+        let mut metrics = Metrics {
+            out: BufWriter::new(file),
+            csv,
+        };
+        if csv && empty {
+            writeln!(metrics.out, "{}", CSV_HEADER).map_err(|err| err.to_string())?;
+        }
+        Ok(metrics)
+    }
+
+    //generation rows are flushed, so a crash loses a generation at most
+    pub fn write(&mut self, record: &Record) -> Result<(), String> {
+        let line = if self.csv {
+            record.csv()
+        } else {
+            serde_json::to_string(record).map_err(|err| err.to_string())?
+        };
+        writeln!(self.out, "{}", line).map_err(|err| err.to_string())?;
+        if let Record::Generation(_) = record {
+            self.out.flush().map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_line_up_with_the_header() {
+        let columns = CSV_HEADER.split(',').count();
+        let life = Record::Life(LifeRecord {
+            life: 3,
+            generation: 0,
+            brain: 2,
+            trial: 1,
+            fitness: 41.0,
+            steps: 60,
+            turns: 19,
+            death: Death::Rocks,
+        });
+        let generation = Record::Generation(GenerationRecord {
+            generation: 1,
+            lives: 10,
+            best: 50.0,
+            mean: 20.5,
+            worst: 1.0,
+            std_dev: 3.25,
+            diversity: 0.5,
+        });
+        assert_eq!(life.csv(), "life,3,0,2,1,41,60,19,rocks,,,,,");
+        assert_eq!(life.csv().split(',').count(), columns);
+        assert_eq!(generation.csv().split(',').count(), columns);
+        assert_eq!(generation.csv().split(',').nth(9), Some("50"));
+
+        let json = serde_json::to_string(&life).unwrap();
+        assert!(json.starts_with(r#"{"record":"life","life":3,"#));
+        assert!(json.ends_with(r#""death":"rocks"}"#));
+    }
+}
@@ -0,0 +1,210 @@
+//Replay -- one saved brain driving life after life.
+//
+//Nothing evolves: no mutation, no selection, the brain keeps
+//its genome and only its fitness is reset between lives. Each
+//life starts from the next of the start positions, round and
+//round, or where the config's trials would start it if none were
+//given, life k as trial k of its brain.
+
+use crate::Simulation;
+use brain::Brain;
+use constants::SimConfig;
+use log::info;
+use mover::{pt2, Death, Point2};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+//where a life starts. no heading means a random one
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Start {
+    pub position: Point2,
+    pub angle_index: Option<usize>,
+}
+
+//"x,y" or "x,y,angle" with angle an index into the 8 headings
+impl FromStr for Start {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let bad = || format!("start must be x,y or x,y,angle: {}", s);
+        let parts: Vec<&str> = s.split(',').map(str::trim).collect();
+        if parts.len() != 2 && parts.len() != 3 {
+            return Err(bad());
+        }
+        let x = parts[0].parse().map_err(|_| bad())?;
+        let y = parts[1].parse().map_err(|_| bad())?;
+        let angle_index = match parts.get(2) {
+            Some(angle) => {
+                let angle: usize = angle.parse().map_err(|_| bad())?;
+                if angle >= constants::NUM_ANGLES {
+                    return Err(format!(
+                        "start angle must be below {}: {}",
+                        constants::NUM_ANGLES,
+                        s
+                    ));
+                }
+                Some(angle)
+            }
+            None => None,
+        };
+        Ok(Start {
+            position: pt2(x, y),
+            angle_index,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub starts: Vec<Start>,
+    pub lives: usize,
+}
+
+impl Simulation {
+    //brain drives lives lives, nothing else changes
+    pub fn replay(
+        config: SimConfig,
+        seed: u64,
+        brain: Brain,
+        replay: Replay,
+    ) -> Result<Self, String> {
+        let mut sim = Simulation::new(config, seed);
+        sim.check_brain(&brain)?;
+        for start in &replay.starts {
+            let p = start.position;
+            if !sim.arena.walls.contains(p) {
+                return Err(format!("start {},{} is outside the arena", p.x, p.y));
+            }
+            if sim.arena.hit(p) == Some(Death::Rocks) {
+                return Err(format!("start {},{} is on an obstacle", p.x, p.y));
+            }
+        }
+        let m = &mut sim.mover;
+        m.brain = brain;
+        m.brain.fitness = 0.0;
+        m.network.reset(&m.brain);
+        sim.replay = Some(replay);
+        sim.start_replay_life();
+        Ok(sim)
+    }
+
+    //all the lives asked for have been lived
+    pub fn finished(&self) -> bool {
+        match &self.replay {
+            Some(replay) => self.num_epochs >= replay.lives,
+            None => false,
+        }
+    }
+
+    pub(crate) fn end_of_replay_life(&mut self) {
+        info!(
+            "REPLAY LIFE: {} FITNESS: {}",
+            self.num_epochs + 1,
+            self.mover.brain.fitness
+        );
+        self.loop_knt = 0;
+        self.mover
+            .reset_mover(self.arena.walls.w(), self.arena.walls.h(), &mut self.rng);
+        self.num_epochs += 1;
+        self.trial = self.num_epochs % self.config.trials;
+        self.start_trial();
+        self.start_replay_life();
+    }
+
+    //move the rover to the --start for this life, if there are any.
+    //start_trial has placed it already otherwise
+    fn start_replay_life(&mut self) {
+        let starts = match &self.replay {
+            Some(replay) if !replay.starts.is_empty() => &replay.starts,
+            _ => return,
+        };
+        let start = starts[self.num_epochs % starts.len()];
+        let angle_index = match start.angle_index {
+            Some(angle_index) => angle_index,
+            None => self.rng.gen_range(0..constants::NUM_ANGLES),
+        };
+        self.mover.place(start.position, angle_index);
+    }
+} //end of impl Simulation
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_starts() {
+        let start: Start = "100,-50.5".parse().unwrap();
+        assert_eq!(start.position, pt2(100.0, -50.5));
+        assert_eq!(start.angle_index, None);
+        let start: Start = "0, 200, 3".parse().unwrap();
+        assert_eq!(start.angle_index, Some(3));
+        assert!("1".parse::<Start>().is_err());
+        assert!("1,2,8".parse::<Start>().is_err());
+        assert!("a,2".parse::<Start>().is_err());
+    }
+
+    #[test]
+    fn replay_keeps_the_genome() {
+        let config = SimConfig {
+            max_loop_knt: 50,
+            ..SimConfig::default()
+        };
+        let brain = Simulation::new(config.clone(), 3).mover.population.brains[1].clone();
+        let start = Start {
+            position: pt2(150.0, 150.0),
+            angle_index: Some(2),
+        };
+        let replay = Replay {
+            starts: vec![start],
+            lives: 3,
+        };
+        let mut sim = Simulation::replay(config.clone(), 5, brain.clone(), replay.clone()).unwrap();
+        assert_eq!(sim.mover.position, start.position);
+        let population = sim.mover.population.clone();
+        while !sim.finished() {
+            sim.step();
+        }
+        assert_eq!(sim.num_epochs, 3);
+        assert!(sim.mover.brain.same_genome(&brain));
+        assert_eq!(sim.mover.position, start.position);
+        assert_eq!(sim.mover.angle_index, 2);
+        assert!(sim.mover.population.brains[0].same_genome(&population.brains[0]));
+
+        let outside = Replay {
+            starts: vec!["5000,0".parse().unwrap()],
+            lives: 1,
+        };
+        assert!(Simulation::replay(config.clone(), 5, brain.clone(), outside).is_err());
+        let on_the_box = Replay {
+            starts: vec!["0,0".parse().unwrap()],
+            lives: 1,
+        };
+        assert!(Simulation::replay(config, 5, brain, on_the_box).is_err());
+    }
+
+    #[test]
+    fn every_replay_life_starts_like_a_trial() {
+        let config = SimConfig {
+            max_loop_knt: 20,
+            trials: 2,
+            start_positions: vec![[150.0, 150.0], [-150.0, -150.0]],
+            start_headings: vec![1, 5],
+            ..SimConfig::default()
+        };
+        let brain = Simulation::new(config.clone(), 3).mover.population.brains[0].clone();
+        let replay = Replay {
+            starts: Vec::new(),
+            lives: 3,
+        };
+        let mut sim = Simulation::replay(config, 5, brain, replay).unwrap();
+        let mut starts = vec![(sim.mover.position, sim.mover.angle_index)];
+        while !sim.finished() {
+            if sim.step() {
+                starts.push((sim.mover.position, sim.mover.angle_index));
+            }
+        }
+        let (a, b) = ((pt2(150.0, 150.0), 1), (pt2(-150.0, -150.0), 5));
+        assert_eq!(starts, vec![a, b, a, b]);
+    }
+}
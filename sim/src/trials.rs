@@ -0,0 +1,187 @@
+//Trials -- a brain lives trials lives in a row before it is scored.
+//
+//One life is a noisy judge: a lucky start and a mediocre brain
+//looks good. With trials > 1 the fitness (and every objective) of
+//a brain is the mean, min or median over its trials, and the GA
+//only sees that. Each trial starts from the next start pose:
+//
+//  fixed       start_positions in turn, the original spot if none
+//  random      anywhere clear in the arena
+//  stratified  trial k in cell k of a grid over the arena, so the
+//              trials of a brain cover all of it
+//
+//start_headings are used in turn if given, otherwise the heading
+//is random as it always was.
+
+use crate::Simulation;
+use constants::{AggregateKind, StartKind};
+use log::{debug, warn};
+use mover::{pt2, Point2};
+use rand::Rng;
+use std::cmp::Ordering;
+
+//the scores of one trial: fitness, then the objectives
+pub type TrialScore = (f32, Vec<f32>);
+
+pub fn aggregate(kind: AggregateKind, values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    match kind {
+        AggregateKind::Mean => values.iter().sum::<f32>() / values.len() as f32,
+        AggregateKind::Min => values.iter().copied().fold(f32::INFINITY, f32::min),
+        AggregateKind::Median => {
+            let mut sorted = values.to_vec();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            let mid = sorted.len() / 2;
+            if sorted.len() % 2 == 1 {
+                sorted[mid]
+            } else {
+                (sorted[mid - 1] + sorted[mid]) / 2.0
+            }
+        }
+    }
+}
+
+//tries before a grid cell (or the arena) full of rocks is given up on
+const MAX_TRIES: usize = 100;
+
+impl Simulation {
+    //more trials to go for this brain. keep its score, start the
+    //next one with the same brain
+    pub(crate) fn next_trial(&mut self) {
+        let m = &mut self.mover;
+        debug!(target: "ga", "TRIAL: {} FITNESS WAS: {}", self.trial, m.brain.fitness);
+        self.trial_scores
+            .push((m.brain.fitness, m.brain.objectives.clone()));
+        self.trial += 1;
+        self.loop_knt = 0;
+        m.reset_mover(self.arena.walls.w(), self.arena.walls.h(), &mut self.rng);
+        self.start_trial();
+    }
+
+    //the brain's score over all of its trials, the last one is
+    //still on the brain
+    pub(crate) fn score_trials(&mut self) {
+        if self.trial_scores.is_empty() {
+            return;
+        }
+        let m = &mut self.mover;
+        self.trial_scores
+            .push((m.brain.fitness, m.brain.objectives.clone()));
+        let kind = self.config.aggregate;
+        let fitness: Vec<f32> = self.trial_scores.iter().map(|(f, _)| *f).collect();
+        m.brain.fitness = aggregate(kind, &fitness);
+        for (k, objective) in m.brain.objectives.iter_mut().enumerate() {
+            let values: Vec<f32> = self
+                .trial_scores
+                .iter()
+                .filter_map(|(_, o)| o.get(k).copied())
+                .collect();
+            *objective = aggregate(kind, &values);
+        }
+        self.trial_scores.clear();
+        self.trial = 0;
+    }
+
+    //put the rover at the start pose of this trial. the original
+    //spot and a random heading are already set by reset_mover
+    pub(crate) fn start_trial(&mut self) {
+        let k = self.trial;
+        let positions = &self.config.start_positions;
+        let position = match self.config.start {
+            StartKind::Fixed if positions.is_empty() => None,
+            StartKind::Fixed => {
+                let [x, y] = positions[k % positions.len()];
+                Some(pt2(x, y))
+            }
+            StartKind::Random => self.clear_point(None),
+            StartKind::Stratified => {
+                let side = (self.config.trials as f32).sqrt().ceil() as usize;
+                self.clear_point(Some((k % side, k / side % side, side)))
+            }
+        };
+        if position.is_none() && self.config.start != StartKind::Fixed {
+            warn!("NO CLEAR START FOUND, TRIAL {} STARTS AT THE USUAL SPOT", k);
+        }
+        let heading = match self.config.start_headings.len() {
+            0 => None,
+            n => Some(self.config.start_headings[k % n]),
+        };
+        if position.is_none() && heading.is_none() {
+            return;
+        }
+        let m = &mut self.mover;
+        let angle_index = heading.unwrap_or(m.angle_index);
+        m.place(position.unwrap_or(m.position), angle_index);
+    }
+
+    //a random spot that doesn't hit anything, in cell (col, row) of
+    //a side by side grid if given, then anywhere. None if there is
+    //still nothing after MAX_TRIES each
+    fn clear_point(&mut self, cell: Option<(usize, usize, usize)>) -> Option<Point2> {
+        let (rng, arena) = (&mut self.rng, &self.arena);
+        let rect = arena.walls;
+        let mut pick = |cell: Option<(usize, usize, usize)>| {
+            let (x0, y0, w, h) = match cell {
+                Some((col, row, side)) => {
+                    let (w, h) = (rect.w() / side as f32, rect.h() / side as f32);
+                    (
+                        rect.left() + col as f32 * w,
+                        rect.bottom() + row as f32 * h,
+                        w,
+                        h,
+                    )
+                }
+                None => (rect.left(), rect.bottom(), rect.w(), rect.h()),
+            };
+            pt2(x0 + rng.gen_range(0.0..w), y0 + rng.gen_range(0.0..h))
+        };
+        for &cell in &[cell, None] {
+            for _ in 0..MAX_TRIES {
+                let p = pick(cell);
+                if arena.hit(p).is_none() {
+                    return Some(p);
+                }
+            }
+        }
+        None
+    }
+} //end of impl Simulation
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use constants::{Obstacle, SimConfig};
+
+    #[test]
+    fn aggregates() {
+        let values = [4.0, 1.0, 10.0, 3.0];
+        assert_eq!(aggregate(AggregateKind::Mean, &values), 4.5);
+        assert_eq!(aggregate(AggregateKind::Min, &values), 1.0);
+        assert_eq!(aggregate(AggregateKind::Median, &values), 3.5);
+        assert_eq!(aggregate(AggregateKind::Median, &values[..3]), 4.0);
+        assert_eq!(aggregate(AggregateKind::Mean, &[]), 0.0);
+    }
+
+    #[test]
+    fn no_clear_spot_falls_back_to_the_usual_start() {
+        //validate would turn this down, a hand built config could not
+        let config = SimConfig {
+            trials: 2,
+            start: StartKind::Random,
+            max_loop_knt: 5,
+            obstacles: vec![Obstacle::Box {
+                x: 0.0,
+                y: 0.0,
+                w: 1000.0,
+                h: 1000.0,
+            }],
+            ..SimConfig::default()
+        };
+        let mut sim = Simulation::new(config, 1);
+        assert_eq!(sim.clear_point(None), None);
+        sim.run_epochs(2);
+        assert_eq!(sim.num_epochs, 2);
+    }
+}